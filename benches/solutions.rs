@@ -0,0 +1,242 @@
+//! Benchmarks every registered solver against its bundled input, timing
+//! the parse and solve phases separately.
+//!
+//! Once criterion has finished, the mean time of every benchmark that ran is
+//! written to `target/bench-results.json` and compared against
+//! `target/bench-baseline.json`. Any benchmark more than `BENCH_THRESHOLD`
+//! percent (default 10) slower than its baseline fails the run. Set
+//! `BENCH_SAVE_BASELINE=1` to merge this run's results into the baseline
+//! instead.
+//!
+//! Timings only mean something on the machine that took them, so the
+//! baseline lives in the target directory rather than in the repository:
+//! record one on this machine before making changes, then compare against it.
+//!
+//! Stub parts are never benchmarked, and the parts in `SLOW` only run when
+//! `BENCH_SLOW=1` is set, since each of them takes minutes per iteration.
+//! A missing baseline fails the run unless it is being saved.
+//!
+//! Criterion's usual filters work, e.g. `cargo bench -- day17`.
+
+use advent_2023::SOLVERS;
+use criterion::{black_box, Criterion};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::{env, fs, process};
+
+/// Parts, as `(day, part)`, too slow on the bundled inputs to benchmark by
+/// default.
+const SLOW: &[(u32, u32)] = &[(14, 2), (16, 2), (20, 2), (21, 2), (23, 1), (23, 2)];
+
+fn target_dir() -> PathBuf {
+    env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("target"))
+}
+
+fn baseline_path() -> PathBuf {
+    target_dir().join("bench-baseline.json")
+}
+
+fn read_input(day: u32) -> String {
+    let path = format!("input/2023/day{}.txt", day);
+    let contents = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+
+    contents.trim_end_matches('\n').to_string()
+}
+
+/// Registers a parse benchmark for each day and a solve benchmark for each
+/// part, returning the ids of every benchmark registered.
+fn bench_solvers(c: &mut Criterion, include_slow: bool) -> Vec<(String, String)> {
+    let mut days = SOLVERS.iter().map(|s| s.day).collect::<Vec<_>>();
+    days.dedup();
+
+    let mut ids = Vec::new();
+
+    for day in days {
+        let group_name = format!("day{}", day);
+        let solvers = SOLVERS.iter().filter(|s| s.day == day).collect::<Vec<_>>();
+        let input = read_input(day);
+
+        // every part of a day shares the same parser
+        let parsed = match solvers[0].parse(&input) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                eprintln!("skipping {}: failed to parse input: {}", group_name, e);
+                continue;
+            }
+        };
+
+        let mut group = c.benchmark_group(&group_name);
+
+        group.bench_function("parse", |b| {
+            b.iter(|| solvers[0].parse(black_box(&input)).unwrap())
+        });
+        ids.push((group_name.clone(), "parse".to_string()));
+
+        for solver in solvers {
+            if solver.stub || (!include_slow && SLOW.contains(&(solver.day, solver.part))) {
+                continue;
+            }
+
+            let name = format!("part{}", solver.part);
+
+            group.bench_function(&name, |b| {
+                b.iter(|| solver.solve(black_box(parsed.as_ref())).unwrap())
+            });
+            ids.push((group_name.clone(), name));
+        }
+
+        group.finish();
+    }
+
+    ids
+}
+
+/// Mean time in nanoseconds of each benchmark criterion measured since
+/// `since`, read back from criterion's own estimates.
+fn collect_results(ids: &[(String, String)], since: SystemTime) -> BTreeMap<String, f64> {
+    let criterion_dir = env::var_os("CRITERION_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| target_dir().join("criterion"));
+
+    let mut results = BTreeMap::new();
+
+    for (group, function) in ids {
+        let path = criterion_dir
+            .join(group)
+            .join(function)
+            .join("new")
+            .join("estimates.json");
+
+        let is_fresh = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .map(|modified| modified >= since)
+            .unwrap_or(false);
+
+        if !is_fresh {
+            continue;
+        }
+
+        let estimates: Value = match fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+        {
+            Some(estimates) => estimates,
+            None => continue,
+        };
+
+        if let Some(mean) = estimates["mean"]["point_estimate"].as_f64() {
+            results.insert(format!("{}/{}", group, function), mean);
+        }
+    }
+
+    results
+}
+
+fn read_baseline() -> Result<BTreeMap<String, f64>, String> {
+    let contents = fs::read_to_string(baseline_path()).map_err(|e| e.to_string())?;
+
+    serde_json::from_str(&contents).map_err(|e| e.to_string())
+}
+
+/// Writes the results alongside their baseline, returning the ids of any
+/// benchmark that regressed by more than `threshold` percent.
+fn compare(
+    results: &BTreeMap<String, f64>,
+    baseline: &BTreeMap<String, f64>,
+    threshold: f64,
+) -> Vec<String> {
+    let mut report = Map::new();
+    let mut regressions = Vec::new();
+
+    for (id, mean_ns) in results {
+        let baseline_ns = baseline.get(id).copied();
+        if baseline_ns.is_none() {
+            eprintln!("no baseline for {}", id);
+        }
+
+        let change = baseline_ns.map(|b| (mean_ns - b) / b * 100.0);
+        let regressed = change.is_some_and(|c| c > threshold);
+
+        if regressed {
+            regressions.push(id.clone());
+        }
+
+        report.insert(
+            id.clone(),
+            json!({
+                "mean_ns": mean_ns,
+                "baseline_ns": baseline_ns,
+                "change_percent": change,
+                "regressed": regressed,
+            }),
+        );
+    }
+
+    let path = target_dir().join("bench-results.json");
+    let report = json!({ "threshold_percent": threshold, "results": report });
+
+    fs::write(&path, serde_json::to_string_pretty(&report).unwrap())
+        .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    println!("results written to {}", path.display());
+
+    regressions
+}
+
+fn main() {
+    let threshold = env::var("BENCH_THRESHOLD")
+        .ok()
+        .map(|t| t.parse::<f64>().expect("BENCH_THRESHOLD must be a number"))
+        .unwrap_or(10.0);
+    let save_baseline = env::var_os("BENCH_SAVE_BASELINE").is_some();
+    let include_slow = env::var_os("BENCH_SLOW").is_some();
+
+    let start = SystemTime::now();
+
+    // some solvers take minutes per iteration, so keep samples to the minimum
+    let mut c = Criterion::default().sample_size(10).configure_from_args();
+    let ids = bench_solvers(&mut c, include_slow);
+    c.final_summary();
+
+    let results = collect_results(&ids, start);
+
+    if results.is_empty() {
+        return;
+    }
+
+    if save_baseline {
+        let path = baseline_path();
+        let mut baseline = read_baseline().unwrap_or_default();
+        baseline.extend(results);
+        fs::write(
+            &path,
+            serde_json::to_string_pretty(&baseline).unwrap() + "\n",
+        )
+        .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        println!("baseline saved to {}", path.display());
+        return;
+    }
+
+    let baseline = match read_baseline() {
+        Ok(baseline) => baseline,
+        Err(e) => {
+            eprintln!("can't read {}: {}", baseline_path().display(), e);
+            eprintln!("run with BENCH_SAVE_BASELINE=1 to record one");
+            process::exit(1);
+        }
+    };
+
+    let regressions = compare(&results, &baseline, threshold);
+
+    if !regressions.is_empty() {
+        eprintln!("regressed by more than {}%:", threshold);
+        for id in &regressions {
+            eprintln!("  {}", id);
+        }
+
+        process::exit(1);
+    }
+}
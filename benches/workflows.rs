@@ -0,0 +1,64 @@
+//! Compares the day 19 workflow interpreter with the compiled program on a
+//! million random parts run through the bundled input's workflows.
+//!
+//! Run with `cargo bench --bench workflows`.
+
+use advent_2023::day19::{Day19, Part, System};
+use advent_2023::Solution;
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::fs;
+
+const PARTS: usize = 1_000_000;
+
+fn system() -> System {
+    let path = "input/2023/day19.txt";
+    let input = fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+
+    Day19::parse(input.trim_end_matches('\n')).unwrap()
+}
+
+fn random_parts(fields: usize, count: usize) -> Vec<Part> {
+    let mut rng = StdRng::seed_from_u64(19);
+
+    (0..count)
+        .map(|_| Part::new((0..fields).map(|_| rng.gen_range(1..=4000)).collect()))
+        .collect()
+}
+
+fn bench_workflows(c: &mut Criterion) {
+    let system = system();
+    let program = system.compile();
+    let parts = random_parts(system.fields().len(), PARTS);
+
+    let mut group = c.benchmark_group("day19_workflows");
+    group.sample_size(10);
+
+    group.bench_function("compile", |b| b.iter(|| black_box(&system).compile()));
+
+    group.throughput(Throughput::Elements(PARTS as u64));
+
+    group.bench_function("interpreter", |b| {
+        b.iter(|| {
+            black_box(&parts)
+                .iter()
+                .filter(|part| system.accepts(part) == Ok(true))
+                .count()
+        })
+    });
+
+    group.bench_function("compiled", |b| {
+        b.iter(|| {
+            black_box(&parts)
+                .iter()
+                .filter(|part| program.accepts(part) == Ok(true))
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_workflows);
+criterion_main!(benches);
@@ -0,0 +1,9 @@
+#![no_main]
+
+use advent_2023::day11::Day11;
+use advent_2023::Solution;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = Day11::parse(input);
+});
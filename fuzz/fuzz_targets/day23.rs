@@ -0,0 +1,9 @@
+#![no_main]
+
+use advent_2023::day23::Day23;
+use advent_2023::Solution;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = Day23::parse(input);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use advent_2023::day9::Day9;
+use advent_2023::Solution;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = Day9::parse(input);
+});
@@ -0,0 +1,225 @@
+use crate::render::{render_grid, Cell, Color, Style};
+use crate::solution::{self, Solution};
+use crate::{day14, day16, day20};
+use std::io::{self, Write};
+
+/// One intermediate state of a simulation, drawn as rows of cells.
+pub type Frame = Vec<Vec<Cell>>;
+
+/// A simulation whose intermediate states can be played back.
+pub trait Animate {
+    fn frames(&self) -> Box<dyn Iterator<Item = Frame> + '_>;
+}
+
+fn animate_input<S>(input: &str) -> solution::Result<Vec<Frame>>
+where
+    S: Solution,
+    S::Input: Animate,
+{
+    Ok(S::parse(input)?.frames().collect())
+}
+
+/// Parses a day's input and collects its frames, if that day can be
+/// animated.
+pub fn animate_day(day: u32, input: &str) -> Option<solution::Result<Vec<Frame>>> {
+    let animate = match day {
+        14 => animate_input::<day14::Day14>,
+        16 => animate_input::<day16::Day16>,
+        20 => animate_input::<day20::Day20>,
+        _ => return None,
+    };
+
+    Some(animate(input))
+}
+
+/// Days that [`animate_day`] can play back.
+pub const ANIMATED_DAYS: &[u32] = &[14, 16, 20];
+
+/// Writes each frame as text under a numbered header.
+pub fn write_text<W: Write>(frames: &[Frame], style: Style, out: &mut W) -> io::Result<()> {
+    for (i, frame) in frames.iter().enumerate() {
+        writeln!(out, "-- frame {} --", i)?;
+        write!(
+            out,
+            "{}",
+            render_grid(frame.iter().map(|row| row.iter().copied()), style)
+        )?;
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+impl Color {
+    fn rgb(&self) -> [u8; 3] {
+        match self {
+            Color::Red => [0xe0, 0x40, 0x40],
+            Color::Green => [0x40, 0xc0, 0x40],
+            Color::Yellow => [0xf0, 0xd0, 0x30],
+            Color::Blue => [0x50, 0x70, 0xf0],
+            Color::Magenta => [0xd0, 0x50, 0xd0],
+            Color::Cyan => [0x40, 0xd0, 0xd0],
+        }
+    }
+}
+
+const BACKGROUND: [u8; 3] = [0x10, 0x10, 0x10];
+const FOREGROUND: [u8; 3] = [0xa0, 0xa0, 0xa0];
+
+fn hex(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+fn xml_escape(c: char) -> String {
+    match c {
+        '&' => "&amp;".to_string(),
+        '<' => "&lt;".to_string(),
+        '>' => "&gt;".to_string(),
+        c => c.to_string(),
+    }
+}
+
+fn dimensions(frames: &[Frame]) -> (usize, usize) {
+    let width = frames
+        .iter()
+        .flatten()
+        .map(|row| row.len())
+        .max()
+        .unwrap_or(0);
+    let height = frames.iter().map(|frame| frame.len()).max().unwrap_or(0);
+
+    (width, height)
+}
+
+/// Writes the frames as an SVG of monospaced text that loops through them,
+/// showing each for `delay_ms`.
+pub fn write_svg<W: Write>(frames: &[Frame], delay_ms: u32, out: &mut W) -> io::Result<()> {
+    const CHAR_WIDTH: usize = 8;
+    const LINE_HEIGHT: usize = 14;
+
+    let (width, height) = dimensions(frames);
+    let n = frames.len().max(1);
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="monospace" font-size="13">"#,
+        width * CHAR_WIDTH,
+        height * LINE_HEIGHT
+    )?;
+    writeln!(
+        out,
+        r#"<rect width="100%" height="100%" fill="{}"/>"#,
+        hex(BACKGROUND)
+    )?;
+
+    for (i, frame) in frames.iter().enumerate() {
+        // a frame is displayed from i/n to (i+1)/n of the loop and hidden otherwise
+        writeln!(
+            out,
+            r#"<g display="none"><animate attributeName="display" values="none;inline;none" keyTimes="0;{};{}" dur="{}ms" calcMode="discrete" repeatCount="indefinite"/>"#,
+            i as f64 / n as f64,
+            (i + 1) as f64 / n as f64,
+            delay_ms as usize * n
+        )?;
+
+        for (y, row) in frame.iter().enumerate() {
+            write!(
+                out,
+                r#"<text x="0" y="{}" fill="{}" xml:space="preserve">"#,
+                (y + 1) * LINE_HEIGHT - 3,
+                hex(FOREGROUND)
+            )?;
+
+            // one tspan per run of same-coloured cells keeps large frames small
+            for run in row.chunk_by(|a, b| a.color() == b.color()) {
+                let text = run.iter().map(|c| xml_escape(c.ch())).collect::<String>();
+
+                match run[0].color() {
+                    Some(color) => write!(
+                        out,
+                        r#"<tspan fill="{}">{}</tspan>"#,
+                        hex(color.rgb()),
+                        text
+                    )?,
+                    None => write!(out, "{}", text)?,
+                }
+            }
+
+            writeln!(out, "</text>")?;
+        }
+
+        writeln!(out, "</g>")?;
+    }
+
+    writeln!(out, "</svg>")
+}
+
+const PALETTE: [Color; 6] = [
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+];
+
+/// Index of a cell's colour in the GIF palette: the background for empty
+/// cells, grey for anything else unhighlighted.
+fn palette_index(cell: Option<&Cell>) -> u8 {
+    match cell {
+        None => 0,
+        Some(cell) => match cell.color() {
+            None if cell.ch() == '.' || cell.ch() == ' ' => 0,
+            None => 1,
+            Some(color) => 2 + PALETTE.iter().position(|c| *c == color).unwrap() as u8,
+        },
+    }
+}
+
+/// Writes the frames as a looping GIF with one coloured block per cell,
+/// showing each for `delay_ms`.
+pub fn write_gif<W: Write>(frames: &[Frame], delay_ms: u32, out: &mut W) -> io::Result<()> {
+    const SCALE: usize = 4;
+
+    let (width, height) = dimensions(frames);
+    let (pixel_width, pixel_height) = (width * SCALE, height * SCALE);
+
+    if pixel_width > u16::MAX as usize || pixel_height > u16::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "frames are too large for a GIF",
+        ));
+    }
+
+    let palette = [BACKGROUND, FOREGROUND]
+        .into_iter()
+        .chain(PALETTE.iter().map(|c| c.rgb()))
+        .flatten()
+        .collect::<Vec<_>>();
+
+    let mut encoder = gif::Encoder::new(out, pixel_width as u16, pixel_height as u16, &palette)
+        .map_err(io::Error::other)?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(io::Error::other)?;
+
+    for frame in frames {
+        let mut pixels = vec![0u8; pixel_width * pixel_height];
+
+        for (py, pixel_row) in pixels.chunks_mut(pixel_width).enumerate() {
+            let row = frame.get(py / SCALE);
+
+            for (px, pixel) in pixel_row.iter_mut().enumerate() {
+                *pixel = palette_index(row.and_then(|row| row.get(px / SCALE)));
+            }
+        }
+
+        let mut gif_frame =
+            gif::Frame::from_indexed_pixels(pixel_width as u16, pixel_height as u16, pixels, None);
+        gif_frame.delay = (delay_ms / 10) as u16;
+
+        encoder.write_frame(&gif_frame).map_err(io::Error::other)?;
+    }
+
+    Ok(())
+}
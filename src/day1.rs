@@ -1,7 +1,7 @@
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
 use regex::Regex;
 
-#[aoc_generator(day1)]
 pub fn input_generator(input: &str) -> Vec<String> {
     input.lines().map(|s| s.to_string()).collect()
 }
@@ -73,13 +73,13 @@ impl CalibrationValue {
             if c.is_alphabetic() {
                 let current_word = &s[..i + 1];
 
-                if let Some(end_match) = re_end.find(&current_word) {
+                if let Some(end_match) = re_end.find(current_word) {
                     last_word_match = Some(end_match);
                 }
             }
         }
 
-        if let Some(last_word_match) = last_word_match.clone() {
+        if let Some(last_word_match) = last_word_match {
             if last_numeric_loc.is_none() || last_word_match.end() > last_numeric_loc.unwrap() {
                 last_numeric = word_to_number(last_word_match.as_str());
             }
@@ -103,7 +103,6 @@ impl CalibrationValue {
     }
 }
 
-#[aoc(day1, part1)]
 pub fn part1(input: &[String]) -> u32 {
     input
         .iter()
@@ -114,7 +113,6 @@ pub fn part1(input: &[String]) -> u32 {
         .sum()
 }
 
-#[aoc(day1, part2)]
 pub fn part2(input: &[String]) -> u32 {
     input
         .iter()
@@ -124,3 +122,38 @@ pub fn part2(input: &[String]) -> u32 {
         })
         .sum()
 }
+
+pub struct Day1;
+
+impl Solution for Day1 {
+    const DAY: u32 = 1;
+
+    type Input = Vec<String>;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        Ok(input_generator(input))
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
+#[aoc_generator(day1)]
+fn generator(input: &str) -> solution::Result<<Day1 as Solution>::Input> {
+    Day1::parse(input)
+}
+
+#[aoc(day1, part1)]
+fn run_part1(input: &<Day1 as Solution>::Input) -> solution::Result<Answer> {
+    Day1::part1(input)
+}
+
+#[aoc(day1, part2)]
+fn run_part2(input: &<Day1 as Solution>::Input) -> solution::Result<Answer> {
+    Day1::part2(input)
+}
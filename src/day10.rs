@@ -1,3 +1,5 @@
+use crate::render::{Cell, Color, Render};
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::str::FromStr;
 
@@ -22,13 +24,14 @@ enum Tile {
 }
 
 #[derive(Debug, Clone)]
-struct Map {
+pub struct Map {
     tiles: Vec<Vec<Tile>>,
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum TileType {
     Pipe,
+    Enclosed,
 }
 
 impl Map {
@@ -197,7 +200,20 @@ impl Map {
         left_distance / 2
     }
 
-    fn count_enclosed_tiles(&self) -> usize {
+    /// Whether a tile on the loop has a connection to the tile above it.
+    fn connects_north(&self, tile: &Tile) -> bool {
+        match tile {
+            Tile::Vertical | Tile::NorthToEast | Tile::NorthToWest => true,
+            Tile::StartPosition => {
+                let (a, b) = self.start_directions();
+                a == Direction::Up || b == Direction::Up
+            }
+            _ => false,
+        }
+    }
+
+    /// Marks every tile that is part of the loop or enclosed by it.
+    fn classify_tiles(&self) -> Vec<Vec<Option<TileType>>> {
         let (left, _) = self.start_directions();
         let mut tilemap = vec![vec![None; self.tiles[0].len()]; self.tiles.len()];
 
@@ -205,7 +221,83 @@ impl Map {
             tilemap[y][x] = Some(TileType::Pipe);
         });
 
-        0
+        // a tile is enclosed if a ray to its left crosses the loop an odd
+        // number of times, counting only pipes that reach up out of the row
+        for (y, row) in tilemap.iter_mut().enumerate() {
+            let mut inside = false;
+
+            for (x, tile_type) in row.iter_mut().enumerate() {
+                match tile_type {
+                    Some(TileType::Pipe) if self.connects_north(&self.tiles[y][x]) => {
+                        inside = !inside;
+                    }
+                    Some(TileType::Pipe) => {}
+                    _ if inside => *tile_type = Some(TileType::Enclosed),
+                    _ => {}
+                }
+            }
+        }
+
+        tilemap
+    }
+
+    fn count_enclosed_tiles(&self) -> usize {
+        self.classify_tiles()
+            .iter()
+            .flatten()
+            .filter(|t| **t == Some(TileType::Enclosed))
+            .count()
+    }
+}
+
+impl Tile {
+    fn symbol(&self) -> char {
+        match self {
+            Tile::Horizontal => '-',
+            Tile::Vertical => '|',
+            Tile::NorthToEast => 'L',
+            Tile::NorthToWest => 'J',
+            Tile::SouthToWest => '7',
+            Tile::SouthToEast => 'F',
+            Tile::None => '.',
+            Tile::StartPosition => 'S',
+        }
+    }
+
+    fn box_symbol(&self) -> char {
+        match self {
+            Tile::Horizontal => '─',
+            Tile::Vertical => '│',
+            Tile::NorthToEast => '└',
+            Tile::NorthToWest => '┘',
+            Tile::SouthToWest => '┐',
+            Tile::SouthToEast => '┌',
+            Tile::None => '.',
+            Tile::StartPosition => 'S',
+        }
+    }
+}
+
+/// The loop is drawn with box-drawing characters and enclosed tiles are
+/// marked `I`.
+impl Render for Map {
+    fn cells(&self) -> Vec<Vec<Cell>> {
+        let tilemap = self.classify_tiles();
+
+        let rows = self.tiles.iter().zip(&tilemap).map(|(tiles, types)| {
+            tiles
+                .iter()
+                .zip(types)
+                .map(|(tile, tile_type)| match tile_type {
+                    Some(TileType::Pipe) => {
+                        Cell::highlighted(tile.box_symbol(), Color::Yellow, tile.box_symbol())
+                    }
+                    Some(TileType::Enclosed) => Cell::highlighted(tile.symbol(), Color::Green, 'I'),
+                    None => Cell::new(tile.symbol()),
+                })
+        });
+
+        rows.map(Iterator::collect).collect()
     }
 }
 
@@ -228,7 +320,7 @@ impl FromStr for Map {
                     'F' => Tile::SouthToEast,
                     '.' => Tile::None,
                     'S' => Tile::StartPosition,
-                    _ => Tile::None,
+                    _ => return Err(()),
                 };
 
                 row.push(tile);
@@ -241,17 +333,45 @@ impl FromStr for Map {
     }
 }
 
+fn part1(map: &Map) -> usize {
+    map.max_distance_from_start()
+}
+
+fn part2(map: &Map) -> usize {
+    map.count_enclosed_tiles()
+}
+
+pub struct Day10;
+
+impl Solution for Day10 {
+    const DAY: u32 = 10;
+
+    type Input = Map;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input.parse().map_err(|_| "invalid pipe map".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
 #[aoc_generator(day10)]
-fn parse_input(input: &str) -> Map {
-    input.parse().unwrap()
+fn generator(input: &str) -> solution::Result<<Day10 as Solution>::Input> {
+    Day10::parse(input)
 }
 
 #[aoc(day10, part1)]
-fn part1(map: &Map) -> usize {
-    map.max_distance_from_start()
+fn run_part1(input: &<Day10 as Solution>::Input) -> solution::Result<Answer> {
+    Day10::part1(input)
 }
 
 #[aoc(day10, part2)]
-fn part2(map: &Map) -> usize {
-    map.count_enclosed_tiles()
+fn run_part2(input: &<Day10 as Solution>::Input) -> solution::Result<Answer> {
+    Day10::part2(input)
 }
@@ -1,6 +1,5 @@
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
-use itertools::Itertools;
-use std::collections::HashSet;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -10,7 +9,7 @@ enum Tile {
 }
 
 #[derive(Debug, Clone)]
-struct StarMap {
+pub struct StarMap {
     tiles: Vec<Vec<Tile>>,
 }
 
@@ -45,36 +44,6 @@ impl StarMap {
         cols
     }
 
-    fn expand(&mut self) {
-        let rows = self.rows_to_expand();
-        let cols = self.cols_to_expand();
-
-        println!("rows: {:?}", rows);
-        println!("cols: {:?}", cols);
-
-        let mut new_tiles = Vec::new();
-
-        for (y, row) in self.tiles.iter().enumerate() {
-            let mut new_row = Vec::new();
-
-            for (x, tile) in row.iter().enumerate() {
-                if cols.contains(&x) {
-                    new_row.push(*tile);
-                }
-
-                new_row.push(*tile);
-            }
-
-            if rows.contains(&y) {
-                new_tiles.push(new_row.clone());
-            }
-
-            new_tiles.push(new_row);
-        }
-
-        self.tiles = new_tiles;
-    }
-
     fn find_galaxies(&self) -> Vec<(usize, usize)> {
         let mut galaxies = Vec::new();
 
@@ -89,60 +58,53 @@ impl StarMap {
         galaxies
     }
 
-    fn shortest_path((y1, x1): (usize, usize), (y2, x2): (usize, usize)) -> usize {
-        (y1 as isize - y2 as isize).unsigned_abs() + (x1 as isize - x2 as isize).unsigned_abs()
-    }
+    /// Maps each coordinate along an axis to its position once every
+    /// empty line before it has been widened to `factor` lines. A factor of
+    /// 0 collapses the empty lines entirely.
+    fn expanded_coords(len: usize, empty: &[usize], factor: usize) -> Vec<usize> {
+        let mut coords = Vec::with_capacity(len);
+        let mut empty_before = 0;
 
-    fn shortest_paths_between_galaxies(&self) -> usize {
-        let galaxies = self.find_galaxies();
+        for i in 0..len {
+            coords.push(i - empty_before + empty_before * factor);
 
-        galaxies
-            .iter()
-            .combinations(2)
-            .map(|pair| {
-                let (y1, x1) = pair[0];
-                let (y2, x2) = pair[1];
+            if empty.binary_search(&i).is_ok() {
+                empty_before += 1;
+            }
+        }
 
-                Self::shortest_path((*y1, *x1), (*y2, *x2))
-            })
-            .sum()
+        coords
     }
 
-    fn shortest_paths_with_expansion(&self, multiple: usize) -> usize {
-        let rows_to_expand = self.rows_to_expand();
-        let cols_to_expand = self.cols_to_expand();
+    /// Sum of pairwise distances between points on a line, in O(n log n).
+    fn sum_of_pairwise_distances(mut positions: Vec<usize>) -> usize {
+        positions.sort_unstable();
 
-        let galaxies = self.find_galaxies();
+        let mut prefix_sum = 0;
+        let mut total = 0;
 
-        galaxies
-            .iter()
-            .combinations(2)
-            .map(|pair| {
-                let (y1, x1) = pair[0];
-                let (y2, x2) = pair[1];
-
-                let expanded_rows = rows_to_expand
-                    .iter()
-                    .filter(|r| **r < *y1 && **r > *y2 || **r < *y2 && **r > *y1)
-                    .collect::<HashSet<_>>();
-                let expanded_cols = cols_to_expand
-                    .iter()
-                    .filter(|c| **c < *x1 && **c > *x2 || **c < *x2 && **c > *x1)
-                    .collect::<HashSet<_>>();
-
-                let mut shortest_path = Self::shortest_path((*y1, *x1), (*y2, *x2));
-
-                if !expanded_rows.is_empty() {
-                    shortest_path += expanded_rows.len() * (multiple - 1);
-                }
+        for (i, p) in positions.into_iter().enumerate() {
+            total += p * i - prefix_sum;
+            prefix_sum += p;
+        }
 
-                if !expanded_cols.is_empty() {
-                    shortest_path += expanded_cols.len() * (multiple - 1);
-                }
+        total
+    }
 
-                shortest_path
-            })
-            .sum()
+    /// Sum of the shortest paths between every pair of galaxies once each
+    /// empty row and column has been widened to `factor` lines, or removed
+    /// when `factor` is 0.
+    pub fn shortest_paths_with_expansion(&self, factor: usize) -> usize {
+        let rows = Self::expanded_coords(self.tiles.len(), &self.rows_to_expand(), factor);
+        let cols = Self::expanded_coords(self.tiles[0].len(), &self.cols_to_expand(), factor);
+
+        let (ys, xs) = self
+            .find_galaxies()
+            .into_iter()
+            .map(|(y, x)| (rows[y], cols[x]))
+            .unzip();
+
+        Self::sum_of_pairwise_distances(ys) + Self::sum_of_pairwise_distances(xs)
     }
 }
 
@@ -155,33 +117,61 @@ impl FromStr for StarMap {
             .map(|line| {
                 line.chars()
                     .map(|c| match c {
-                        '.' => Tile::Empty,
-                        '#' => Tile::Galaxy,
-                        _ => panic!("Invalid tile"),
+                        '.' => Ok(Tile::Empty),
+                        '#' => Ok(Tile::Galaxy),
+                        _ => Err(()),
                     })
-                    .collect()
+                    .collect::<Result<Vec<_>, _>>()
             })
-            .collect();
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if tiles.is_empty() || tiles.iter().any(|row| row.len() != tiles[0].len()) {
+            return Err(());
+        }
 
         Ok(StarMap { tiles })
     }
 }
 
-#[aoc_generator(day11)]
-fn parse_input(input: &str) -> StarMap {
-    input.parse().unwrap()
+fn part1(star_map: &StarMap) -> usize {
+    star_map.shortest_paths_with_expansion(2)
 }
 
-#[aoc(day11, part1)]
-fn part1(input: &StarMap) -> usize {
-    let mut star_map = input.clone();
+fn part2(star_map: &StarMap) -> usize {
+    star_map.shortest_paths_with_expansion(1000000)
+}
 
-    star_map.expand();
+pub struct Day11;
 
-    star_map.shortest_paths_between_galaxies()
+impl Solution for Day11 {
+    const DAY: u32 = 11;
+
+    type Input = StarMap;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input.parse().map_err(|_| "invalid star map".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
+#[aoc_generator(day11)]
+fn generator(input: &str) -> solution::Result<<Day11 as Solution>::Input> {
+    Day11::parse(input)
+}
+
+#[aoc(day11, part1)]
+fn run_part1(input: &<Day11 as Solution>::Input) -> solution::Result<Answer> {
+    Day11::part1(input)
 }
 
 #[aoc(day11, part2)]
-fn part2(star_map: &StarMap) -> usize {
-    star_map.shortest_paths_with_expansion(1000000)
+fn run_part2(input: &<Day11 as Solution>::Input) -> solution::Result<Answer> {
+    Day11::part2(input)
 }
@@ -1,11 +1,38 @@
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
 
-#[aoc_generator(day12)]
 fn parse_input(input: &str) -> String {
     input.to_string()
 }
 
-#[aoc(day12, part1)]
 fn part1(_input: &str) -> usize {
     0
 }
+
+pub struct Day12;
+
+impl Solution for Day12 {
+    const DAY: u32 = 12;
+
+    type Input = String;
+
+    const STUBS: &'static [u32] = &[1];
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        Ok(parse_input(input))
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+}
+
+#[aoc_generator(day12)]
+fn generator(input: &str) -> solution::Result<<Day12 as Solution>::Input> {
+    Day12::parse(input)
+}
+
+#[aoc(day12, part1)]
+fn run_part1(input: &<Day12 as Solution>::Input) -> solution::Result<Answer> {
+    Day12::part1(input)
+}
@@ -1,9 +1,10 @@
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
 use std::cmp::min;
 use std::str::FromStr;
 
 #[derive(Debug, Clone)]
-struct Pattern {
+pub struct Pattern {
     pattern: Vec<Vec<char>>,
 }
 
@@ -89,21 +90,23 @@ impl FromStr for Pattern {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let pattern = s.lines().map(|l| l.chars().collect()).collect::<Vec<_>>();
+        let pattern = s
+            .lines()
+            .map(|l| l.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        if pattern.is_empty() || pattern.iter().any(|row| row.len() != pattern[0].len()) {
+            return Err(());
+        }
 
         Ok(Pattern { pattern })
     }
 }
 
-#[aoc_generator(day13)]
-fn parse_input(input: &str) -> Vec<Pattern> {
-    input
-        .split("\n\n")
-        .map(|l| l.parse().unwrap())
-        .collect::<Vec<_>>()
+fn parse_input(input: &str) -> Result<Vec<Pattern>, ()> {
+    input.split("\n\n").map(|l| l.parse()).collect()
 }
 
-#[aoc(day13, part1)]
 fn part1(input: &[Pattern]) -> usize {
     let reflections = input
         .iter()
@@ -118,3 +121,29 @@ fn part1(input: &[Pattern]) -> usize {
         })
         .sum()
 }
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    const DAY: u32 = 13;
+
+    type Input = Vec<Pattern>;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        parse_input(input).map_err(|_| "invalid pattern".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+}
+
+#[aoc_generator(day13)]
+fn generator(input: &str) -> solution::Result<<Day13 as Solution>::Input> {
+    Day13::parse(input)
+}
+
+#[aoc(day13, part1)]
+fn run_part1(input: &<Day13 as Solution>::Input) -> solution::Result<Answer> {
+    Day13::part1(input)
+}
@@ -1,5 +1,10 @@
+use crate::animate::{Animate, Frame};
+use crate::render::{Cell, Color, Render, Style};
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::iter;
 use std::str::FromStr;
+use tracing::{debug, instrument, trace};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Rock {
@@ -8,7 +13,7 @@ enum Rock {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Platform {
+pub struct Platform {
     width: usize,
     height: usize,
     tiles: Vec<Vec<Option<Rock>>>,
@@ -29,7 +34,8 @@ impl Platform {
         total
     }
 
-    fn tilt_north(&mut self) -> bool {
+    /// Moves every rounded rock one tile north, if it can.
+    fn tilt_north_once(&mut self) -> bool {
         let mut moved = false;
 
         for y in 0..self.height {
@@ -45,10 +51,15 @@ impl Platform {
             }
         }
 
-        moved && self.tilt_north()
+        moved
     }
 
-    fn tilt_east(&mut self) -> bool {
+    fn tilt_north(&mut self) -> bool {
+        self.tilt_north_once() && self.tilt_north()
+    }
+
+    /// Moves every rounded rock one tile east, if it can.
+    fn tilt_east_once(&mut self) -> bool {
         let mut moved = false;
 
         for y in 0..self.height {
@@ -64,10 +75,15 @@ impl Platform {
             }
         }
 
-        moved && self.tilt_east()
+        moved
     }
 
-    fn tilt_south(&mut self) -> bool {
+    fn tilt_east(&mut self) -> bool {
+        self.tilt_east_once() && self.tilt_east()
+    }
+
+    /// Moves every rounded rock one tile south, if it can.
+    fn tilt_south_once(&mut self) -> bool {
         let mut moved = false;
 
         for y in 0..self.height {
@@ -83,10 +99,15 @@ impl Platform {
             }
         }
 
-        moved && self.tilt_south()
+        moved
     }
 
-    fn tilt_west(&mut self) -> bool {
+    fn tilt_south(&mut self) -> bool {
+        self.tilt_south_once() && self.tilt_south()
+    }
+
+    /// Moves every rounded rock one tile west, if it can.
+    fn tilt_west_once(&mut self) -> bool {
         let mut moved = false;
 
         for y in 0..self.height {
@@ -102,14 +123,43 @@ impl Platform {
             }
         }
 
-        moved && self.tilt_west()
+        moved
     }
 
+    fn tilt_west(&mut self) -> bool {
+        self.tilt_west_once() && self.tilt_west()
+    }
+
+    /// The platform after every single step of one spin cycle, starting
+    /// with its current state.
+    fn spin_cycle_states(&self) -> impl Iterator<Item = Platform> {
+        let tilts: [fn(&mut Platform) -> bool; 4] = [
+            Platform::tilt_north_once,
+            Platform::tilt_west_once,
+            Platform::tilt_south_once,
+            Platform::tilt_east_once,
+        ];
+        let mut platform = self.clone();
+        let mut tilt = 0;
+
+        iter::once(self.clone()).chain(iter::from_fn(move || {
+            while tilt < tilts.len() {
+                if tilts[tilt](&mut platform) {
+                    return Some(platform.clone());
+                }
+
+                tilt += 1;
+            }
+
+            None
+        }))
+    }
+
+    #[instrument(level = "debug", skip(self))]
     fn n_cycles(&mut self, n: usize) {
         for i in 0..n {
             if i % 1000 == 0 {
-                println!("Cycle {}", i);
-                self.print();
+                debug!(cycle = i, "platform:\n{}", self.render(Style::Plain));
             }
 
             self.tilt_north();
@@ -117,21 +167,29 @@ impl Platform {
             self.tilt_south();
             self.tilt_east();
 
-            println!("Total load: {}, cycle {}", self.total_load(), i);
+            trace!(cycle = i, load = self.total_load());
         }
     }
+}
 
-    fn print(&self) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                match self.tiles[y][x] {
-                    Some(Rock::Rounded) => print!("O"),
-                    Some(Rock::Cube) => print!("#"),
-                    None => print!("."),
-                }
-            }
-            println!();
-        }
+impl Render for Platform {
+    fn cells(&self) -> Vec<Vec<Cell>> {
+        let rows = self.tiles.iter().map(|row| {
+            row.iter().map(|tile| match tile {
+                Some(Rock::Rounded) => Cell::highlighted('O', Color::Cyan, 'O'),
+                Some(Rock::Cube) => Cell::new('#'),
+                None => Cell::new('.'),
+            })
+        });
+
+        rows.map(Iterator::collect).collect()
+    }
+}
+
+/// One frame per rock-step of a spin cycle.
+impl Animate for Platform {
+    fn frames(&self) -> Box<dyn Iterator<Item = Frame> + '_> {
+        Box::new(self.spin_cycle_states().map(|platform| platform.cells()))
     }
 }
 
@@ -148,13 +206,17 @@ impl FromStr for Platform {
                     '.' => tiles.push(None),
                     'O' => tiles.push(Some(Rock::Rounded)),
                     '#' => tiles.push(Some(Rock::Cube)),
-                    _ => panic!("Invalid character: {}", c),
+                    _ => return Err(()),
                 }
             }
 
             rows.push(tiles);
         }
 
+        if rows.is_empty() || rows.iter().any(|row| row.len() != rows[0].len()) {
+            return Err(());
+        }
+
         Ok(Platform {
             width: rows[0].len(),
             height: rows.len(),
@@ -163,12 +225,6 @@ impl FromStr for Platform {
     }
 }
 
-#[aoc_generator(day14)]
-fn parse_input(input: &str) -> Platform {
-    input.parse().unwrap()
-}
-
-#[aoc(day14, part1)]
 fn part1(platform: &Platform) -> usize {
     let mut platform = platform.clone();
 
@@ -177,13 +233,47 @@ fn part1(platform: &Platform) -> usize {
     platform.total_load()
 }
 
-#[aoc(day14, part2)]
 fn part2(platform: &Platform) -> usize {
     let mut platform = platform.clone();
 
     platform.n_cycles(1000000000);
 
-    platform.print();
+    debug!("final platform:\n{}", platform.render(Style::Plain));
 
     platform.total_load()
 }
+
+pub struct Day14;
+
+impl Solution for Day14 {
+    const DAY: u32 = 14;
+
+    type Input = Platform;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input.parse().map_err(|_| "invalid platform".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
+#[aoc_generator(day14)]
+fn generator(input: &str) -> solution::Result<<Day14 as Solution>::Input> {
+    Day14::parse(input)
+}
+
+#[aoc(day14, part1)]
+fn run_part1(input: &<Day14 as Solution>::Input) -> solution::Result<Answer> {
+    Day14::part1(input)
+}
+
+#[aoc(day14, part2)]
+fn run_part2(input: &<Day14 as Solution>::Input) -> solution::Result<Answer> {
+    Day14::part2(input)
+}
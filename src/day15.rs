@@ -1,12 +1,13 @@
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
-use lazy_static::lazy_static;
-use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Lens {
-    label: String,
-    focal_length: usize,
+pub struct Lens {
+    pub label: String,
+    pub focal_length: usize,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -15,18 +16,38 @@ enum Command {
     RemoveLens(String),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct Step {
-    chars: Vec<char>,
+impl FromStr for Command {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(label) = s.strip_suffix('-') {
+            if label.is_empty() {
+                return Err(());
+            }
+
+            return Ok(Command::RemoveLens(label.to_string()));
+        }
+
+        let (label, focal_length) = s.rsplit_once('=').ok_or(())?;
+
+        if label.is_empty() {
+            return Err(());
+        }
+
+        Ok(Command::AddLens(Lens {
+            label: label.to_string(),
+            focal_length: focal_length.parse().map_err(|_| ())?,
+        }))
+    }
 }
 
-lazy_static! {
-    static ref LABEL_REGEX: Regex = Regex::new(r"([a-zA-Z]+)").unwrap();
-    static ref FOCAL_LENGTH_REGEX: Regex = Regex::new(r"=([0-9]+)").unwrap();
-    static ref REMOVAL_REGEX: Regex = Regex::new(r"-").unwrap();
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    text: String,
+    command: Command,
 }
 
-fn hash(s: &str) -> usize {
+pub fn hash(s: &str) -> usize {
     let mut hash = 0usize;
 
     for c in s.chars() {
@@ -40,92 +61,129 @@ fn hash(s: &str) -> usize {
 
 impl Step {
     fn hash(&self) -> usize {
-        hash(&self.chars.iter().collect::<String>())
-    }
-
-    fn to_command(&self) -> Command {
-        let s = self.chars.iter().collect::<String>();
-
-        let label = LABEL_REGEX
-            .captures_iter(&s)
-            .next()
-            .unwrap()
-            .get(1)
-            .unwrap()
-            .as_str()
-            .to_string();
-
-        if REMOVAL_REGEX.is_match(&s) {
-            Command::RemoveLens(label)
-        } else {
-            let focal_length = FOCAL_LENGTH_REGEX
-                .captures_iter(&s)
-                .next()
-                .unwrap()
-                .get(1)
-                .unwrap()
-                .as_str()
-                .parse()
-                .unwrap();
-
-            Command::AddLens(Lens {
-                label,
-                focal_length,
-            })
+        hash(&self.text)
+    }
+}
+
+impl FromStr for Step {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Step {
+            text: s.to_string(),
+            command: s.parse()?,
+        })
+    }
+}
+
+/// One box's lenses. Removing a lens leaves a gap in its slot rather than
+/// shifting the lenses behind it, and the gaps are squeezed out once they
+/// make up half the slots.
+#[derive(Debug, Clone, Default)]
+struct LensBox {
+    slots: Vec<Option<Lens>>,
+    index: HashMap<String, usize>,
+}
+
+impl LensBox {
+    fn insert(&mut self, lens: Lens) {
+        match self.index.get(&lens.label) {
+            Some(&slot) => self.slots[slot] = Some(lens),
+            None => {
+                self.index.insert(lens.label.clone(), self.slots.len());
+                self.slots.push(Some(lens));
+            }
         }
     }
+
+    fn remove(&mut self, label: &str) -> Option<Lens> {
+        let slot = self.index.remove(label)?;
+        let lens = self.slots[slot].take();
+
+        if self.index.len() * 2 < self.slots.len() {
+            self.slots.retain(Option::is_some);
+
+            for (slot, lens) in self.slots.iter().flatten().enumerate() {
+                *self.index.get_mut(&lens.label).unwrap() = slot;
+            }
+        }
+
+        lens
+    }
+
+    fn lenses(&self) -> impl Iterator<Item = &Lens> {
+        self.slots.iter().flatten()
+    }
 }
 
-struct InitSequence {
-    steps: Vec<Step>,
+/// Boxes are equal when they hold the same lenses in the same order,
+/// wherever the gaps between them happen to be.
+impl PartialEq for LensBox {
+    fn eq(&self, other: &Self) -> bool {
+        self.lenses().eq(other.lenses())
+    }
 }
 
-impl InitSequence {
-    fn hash_sum(&self) -> usize {
-        self.steps.iter().map(|step| step.hash()).sum()
+impl Eq for LensBox {}
+
+/// The HASHMAP: 256 boxes of lenses, keyed by the hash of each lens label.
+///
+/// Each box keeps its lenses in the order they were first inserted.
+/// Inserting a label that is already present replaces its focal length in
+/// place, and removing a lens moves every lens behind it one slot forward.
+/// Both take constant time on average.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LensBoxes {
+    boxes: Vec<LensBox>,
+}
+
+impl Default for LensBoxes {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    fn focusing_power(&self) -> usize {
-        let mut boxes = vec![Vec::<Lens>::new(); 256];
-        let commands = self
-            .steps
-            .iter()
-            .map(|step| step.to_command())
-            .collect::<Vec<_>>();
-
-        for command in commands {
-            match command {
-                Command::RemoveLens(label) => {
-                    let hash = hash(&label);
-                    if let Some((pos, _)) = boxes[hash]
-                        .iter()
-                        .enumerate()
-                        .find(|(_, l)| l.label == label)
-                    {
-                        boxes[hash].remove(pos);
-                    }
-                }
-                Command::AddLens(lens) => {
-                    let hash = hash(&lens.label);
-                    if let Some((pos, _)) = boxes[hash]
-                        .iter()
-                        .enumerate()
-                        .find(|(_, l)| l.label == lens.label)
-                    {
-                        boxes[hash][pos] = lens;
-                    } else {
-                        boxes[hash].push(lens);
-                    }
-                }
+impl LensBoxes {
+    pub fn new() -> Self {
+        LensBoxes {
+            boxes: vec![LensBox::default(); 256],
+        }
+    }
+
+    /// Puts `lens` at the back of its box, or swaps it in for the lens with
+    /// the same label without moving it.
+    pub fn insert(&mut self, lens: Lens) {
+        self.boxes[hash(&lens.label)].insert(lens);
+    }
+
+    /// Takes the lens with `label` out of its box, if it's there.
+    pub fn remove(&mut self, label: &str) -> Option<Lens> {
+        self.boxes[hash(label)].remove(label)
+    }
+
+    fn apply(&mut self, command: &Command) {
+        match command {
+            Command::AddLens(lens) => self.insert(lens.clone()),
+            Command::RemoveLens(label) => {
+                self.remove(label);
             }
         }
+    }
 
-        boxes
+    /// Every non-empty box, along with its box number and its lenses from
+    /// front to back.
+    pub fn iter_boxes(&self) -> impl Iterator<Item = (usize, impl Iterator<Item = &Lens>)> {
+        self.boxes
             .iter()
             .enumerate()
+            .filter(|(_, lenses)| !lenses.index.is_empty())
+            .map(|(box_number, lenses)| (box_number, lenses.lenses()))
+    }
+
+    pub fn focusing_power(&self) -> usize {
+        self.iter_boxes()
             .map(|(box_number, lenses)| {
                 lenses
-                    .iter()
                     .enumerate()
                     .map(|(slot_number, lens)| {
                         (box_number + 1) * (slot_number + 1) * lens.focal_length
@@ -136,6 +194,47 @@ impl InitSequence {
     }
 }
 
+impl fmt::Display for LensBoxes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (box_number, lenses) in self.iter_boxes() {
+            write!(f, "Box {}:", box_number)?;
+
+            for lens in lenses {
+                write!(f, " [{} {}]", lens.label, lens.focal_length)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct InitSequence {
+    steps: Vec<Step>,
+}
+
+impl InitSequence {
+    fn hash_sum(&self) -> usize {
+        self.steps.iter().map(|step| step.hash()).sum()
+    }
+
+    /// The boxes after running every step.
+    fn arrange(&self) -> LensBoxes {
+        let mut boxes = LensBoxes::new();
+
+        for step in &self.steps {
+            boxes.apply(&step.command);
+        }
+
+        boxes
+    }
+
+    fn focusing_power(&self) -> usize {
+        self.arrange().focusing_power()
+    }
+}
+
 impl FromStr for InitSequence {
     type Err = ();
 
@@ -143,26 +242,54 @@ impl FromStr for InitSequence {
         let steps = s
             .trim()
             .split(',')
-            .map(|step| Step {
-                chars: step.chars().collect(),
-            })
-            .collect();
+            .map(|step| step.parse())
+            .collect::<Result<_, _>>()?;
 
         Ok(Self { steps })
     }
 }
 
+fn part1(init_sequence: &InitSequence) -> usize {
+    init_sequence.hash_sum()
+}
+
+fn part2(init_sequence: &InitSequence) -> usize {
+    init_sequence.focusing_power()
+}
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    const DAY: u32 = 15;
+
+    type Input = InitSequence;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input
+            .parse()
+            .map_err(|_| "invalid initialization sequence".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
 #[aoc_generator(day15)]
-fn parse_input(input: &str) -> InitSequence {
-    input.parse().unwrap()
+fn generator(input: &str) -> solution::Result<<Day15 as Solution>::Input> {
+    Day15::parse(input)
 }
 
 #[aoc(day15, part1)]
-fn part1(init_sequence: &InitSequence) -> usize {
-    init_sequence.hash_sum()
+fn run_part1(input: &<Day15 as Solution>::Input) -> solution::Result<Answer> {
+    Day15::part1(input)
 }
 
 #[aoc(day15, part2)]
-fn part2(init_sequence: &InitSequence) -> usize {
-    init_sequence.focusing_power()
+fn run_part2(input: &<Day15 as Solution>::Input) -> solution::Result<Answer> {
+    Day15::part2(input)
 }
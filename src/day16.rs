@@ -1,6 +1,11 @@
+use crate::animate::{Animate, Frame};
+use crate::render::{Cell, Color, Render};
+use crate::solution::{self, Answer, Solution};
+use crate::util::Direction;
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::collections::HashSet;
+use std::iter;
 use std::str::FromStr;
-use crate::util::Direction;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Tile {
@@ -11,14 +16,14 @@ enum Tile {
     BLTRMirror,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct Beam {
     direction: Direction,
     position: (isize, isize),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Grid {
+pub struct Grid {
     tiles: Vec<Vec<Tile>>,
     energized_tiles: Vec<Vec<bool>>,
 }
@@ -41,7 +46,7 @@ impl Grid {
 
             match beam.direction {
                 Direction::Up => {
-                    let position = ((y - 1) as isize, x as isize);
+                    let position = (y - 1, x);
                     if y > 0 {
                         self.energized_tiles[y as usize - 1][x as usize] = true;
                         match self.tiles[y as usize - 1][x as usize] {
@@ -191,7 +196,7 @@ impl Grid {
     }
 
     fn energize_with_initial_beam(&mut self, initial_beam: &Beam) {
-        let mut beams = vec![initial_beam.clone()];
+        let mut beams = vec![*initial_beam];
         let mut last_energized_count = 0;
         let mut steps_since_last_change = 0;
         let total_tiles = self.tiles.len() * self.tiles[0].len();
@@ -217,6 +222,31 @@ impl Grid {
         self.energize_with_initial_beam(&initial_beam);
     }
 
+    /// The grid and the beams still travelling after each step of part 1's
+    /// beam, stopping once every beam is retracing a path already taken.
+    fn beam_states(&self) -> impl Iterator<Item = (Grid, Vec<Beam>)> {
+        let mut grid = self.clone();
+        let mut beams = vec![Beam {
+            direction: Direction::Right,
+            position: (0, -1),
+        }];
+        let mut seen = HashSet::new();
+
+        iter::from_fn(move || {
+            beams = grid
+                .step(&beams)
+                .into_iter()
+                .filter(|beam| seen.insert(*beam))
+                .collect();
+
+            if beams.is_empty() {
+                None
+            } else {
+                Some((grid.clone(), beams.clone()))
+            }
+        })
+    }
+
     fn num_energized_tiles(&self) -> usize {
         self.energized_tiles
             .iter()
@@ -287,6 +317,84 @@ impl Grid {
     }
 }
 
+impl Tile {
+    fn symbol(&self) -> char {
+        match self {
+            Tile::Empty => '.',
+            Tile::VSplit => '|',
+            Tile::HSplit => '-',
+            Tile::TLBRMirror => '\\',
+            Tile::BLTRMirror => '/',
+        }
+    }
+}
+
+impl Grid {
+    /// Energized tiles are marked `#` and the heads of `beams` with the
+    /// direction they travel in.
+    fn cells_with_beams(&self, beams: &[Beam]) -> Vec<Vec<Cell>> {
+        let mut cells = self
+            .tiles
+            .iter()
+            .zip(&self.energized_tiles)
+            .map(|(tiles, energized)| {
+                tiles
+                    .iter()
+                    .zip(energized)
+                    .map(|(tile, energized)| {
+                        if *energized {
+                            Cell::highlighted(tile.symbol(), Color::Yellow, '#')
+                        } else {
+                            Cell::new(tile.symbol())
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        for beam in beams {
+            let (y, x) = beam.position;
+            let arrow = match beam.direction {
+                Direction::Up => '^',
+                Direction::Down => 'v',
+                Direction::Left => '<',
+                Direction::Right => '>',
+            };
+
+            cells[y as usize][x as usize] = Cell::highlighted(arrow, Color::Red, arrow);
+        }
+
+        cells
+    }
+}
+
+/// A grid that has not been energized yet is shown as it would be after
+/// part 1's beam passes through it.
+impl Render for Grid {
+    fn cells(&self) -> Vec<Vec<Cell>> {
+        if self.num_energized_tiles() == 0 {
+            let mut grid = self.clone();
+            grid.energize();
+
+            return grid.cells();
+        }
+
+        self.cells_with_beams(&[])
+    }
+}
+
+/// One frame per step of part 1's beam, with the beams' heads marked.
+impl Animate for Grid {
+    fn frames(&self) -> Box<dyn Iterator<Item = Frame> + '_> {
+        Box::new(
+            iter::once(self.cells_with_beams(&[])).chain(
+                self.beam_states()
+                    .map(|(grid, beams)| grid.cells_with_beams(&beams)),
+            ),
+        )
+    }
+}
+
 impl FromStr for Grid {
     type Err = ();
 
@@ -303,23 +411,21 @@ impl FromStr for Grid {
                     '-' => row.push(Tile::HSplit),
                     '\\' => row.push(Tile::TLBRMirror),
                     '/' => row.push(Tile::BLTRMirror),
-                    _ => panic!("Unknown tile: {}", c),
+                    _ => return Err(()),
                 }
             }
 
             tiles.push(row);
         }
 
+        if tiles.is_empty() || tiles.iter().any(|row| row.len() != tiles[0].len()) {
+            return Err(());
+        }
+
         Ok(Grid::new(tiles))
     }
 }
 
-#[aoc_generator(day16)]
-fn parse_input(input: &str) -> Grid {
-    input.parse().unwrap()
-}
-
-#[aoc(day16, part1)]
 fn part1(grid: &Grid) -> usize {
     let mut grid = grid.clone();
 
@@ -328,7 +434,43 @@ fn part1(grid: &Grid) -> usize {
     grid.num_energized_tiles()
 }
 
-#[aoc(day16, part2)]
 fn part2(grid: &Grid) -> usize {
     grid.max_energy_configuration()
 }
+
+pub struct Day16;
+
+impl Solution for Day16 {
+    const DAY: u32 = 16;
+
+    type Input = Grid;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input
+            .parse()
+            .map_err(|_| "invalid contraption layout".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
+#[aoc_generator(day16)]
+fn generator(input: &str) -> solution::Result<<Day16 as Solution>::Input> {
+    Day16::parse(input)
+}
+
+#[aoc(day16, part1)]
+fn run_part1(input: &<Day16 as Solution>::Input) -> solution::Result<Answer> {
+    Day16::part1(input)
+}
+
+#[aoc(day16, part2)]
+fn run_part2(input: &<Day16 as Solution>::Input) -> solution::Result<Answer> {
+    Day16::part2(input)
+}
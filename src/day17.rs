@@ -1,187 +1,176 @@
+use crate::render::{Cell, Color, Render};
+use crate::solution::{self, Answer, Solution};
 use crate::util::Direction;
 use aoc_runner_derive::{aoc, aoc_generator};
 use pathfinding::prelude::dijkstra;
+use std::collections::HashMap;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Grid {
+pub struct Grid {
     width: usize,
     height: usize,
     data: Vec<Vec<usize>>,
 }
 
+/// How far a crucible must and may travel in a straight line before it
+/// has to turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CrucibleSpec {
+    min_straight: usize,
+    max_straight: usize,
+}
+
+const CRUCIBLE: CrucibleSpec = CrucibleSpec {
+    min_straight: 1,
+    max_straight: 3,
+};
+
+const ULTRA_CRUCIBLE: CrucibleSpec = CrucibleSpec {
+    min_straight: 4,
+    max_straight: 10,
+};
+
+/// A crucible at rest after a straight run, facing the direction it
+/// arrived in. It has no heading before its first move.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Pos {
-    y: isize,
-    x: isize,
-    direction: Direction,
-    steps_taken_in_direction: usize,
+struct Crucible {
+    y: usize,
+    x: usize,
+    heading: Option<Direction>,
 }
 
-impl Pos {
-    fn new(y: isize, x: isize, direction: Direction) -> Self {
-        Self {
-            y,
-            x,
-            direction,
-            steps_taken_in_direction: 0,
+impl Grid {
+    fn step(&self, (y, x): (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        match direction {
+            Direction::Up if y > 0 => Some((y - 1, x)),
+            Direction::Down if y < self.height - 1 => Some((y + 1, x)),
+            Direction::Left if x > 0 => Some((y, x - 1)),
+            Direction::Right if x < self.width - 1 => Some((y, x + 1)),
+            _ => None,
         }
     }
 
-    fn step_in_direction(&self, direction: &Direction, grid: &Grid) -> Option<Pos> {
-        match direction {
-            Direction::Up => {
-                if self.y > 0 {
-                    Some(Pos {
-                        y: self.y - 1,
-                        x: self.x,
-                        direction: Direction::Up,
-                        steps_taken_in_direction: if self.direction == Direction::Up {
-                            self.steps_taken_in_direction + 1
-                        } else {
-                            1
-                        },
-                    })
-                } else {
-                    None
-                }
-            }
-            Direction::Down => {
-                if self.y < (grid.height as isize) - 1 {
-                    Some(Pos {
-                        y: self.y + 1,
-                        x: self.x,
-                        direction: Direction::Down,
-                        steps_taken_in_direction: if self.direction == Direction::Down {
-                            self.steps_taken_in_direction + 1
-                        } else {
-                            1
-                        },
-                    })
-                } else {
-                    None
-                }
-            }
-            Direction::Left => {
-                if self.x > 0 {
-                    Some(Pos {
-                        y: self.y,
-                        x: self.x - 1,
-                        direction: Direction::Left,
-                        steps_taken_in_direction: if self.direction == Direction::Left {
-                            self.steps_taken_in_direction + 1
-                        } else {
-                            1
-                        },
-                    })
-                } else {
-                    None
+    /// Every straight run the crucible can make next, each collapsed into a
+    /// single edge weighted by the heat lost along it. A run must turn away
+    /// from the current heading; from the start, any direction is allowed.
+    fn successors(&self, crucible: &Crucible, spec: &CrucibleSpec) -> Vec<(Crucible, usize)> {
+        let directions = match crucible.heading {
+            None => vec![
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ],
+            Some(heading) => vec![heading.left(), heading.right()],
+        };
+
+        let mut successors = Vec::new();
+
+        for direction in directions {
+            let mut pos = (crucible.y, crucible.x);
+            let mut heat_loss = 0;
+
+            for steps in 1..=spec.max_straight {
+                match self.step(pos, direction) {
+                    Some(next) => pos = next,
+                    None => break,
                 }
-            }
-            Direction::Right => {
-                if self.x < (grid.width as isize) - 1 {
-                    Some(Pos {
-                        y: self.y,
-                        x: self.x + 1,
-                        direction: Direction::Right,
-                        steps_taken_in_direction: if self.direction == Direction::Right {
-                            self.steps_taken_in_direction + 1
-                        } else {
-                            1
+
+                heat_loss += self.data[pos.0][pos.1];
+
+                if steps >= spec.min_straight {
+                    successors.push((
+                        Crucible {
+                            y: pos.0,
+                            x: pos.1,
+                            heading: Some(direction),
                         },
-                    })
-                } else {
-                    None
+                        heat_loss,
+                    ));
                 }
             }
         }
+
+        successors
     }
 
-    fn successors(&self, grid: &Grid) -> Vec<Pos> {
-        let mut successors = Vec::new();
+    /// The least heat lost getting a crucible from the top-left to the
+    /// bottom-right block, along with every block on the path taken.
+    fn minimal_heat_loss(&self, spec: &CrucibleSpec) -> Option<(Vec<(usize, usize)>, usize)> {
+        let start = Crucible {
+            y: 0,
+            x: 0,
+            heading: None,
+        };
 
-        if self.steps_taken_in_direction <= 2 {
-            if let Some(p) = self.step_in_direction(&self.direction, grid) {
-                successors.push(p);
-            }
+        let goal = (self.height - 1, self.width - 1);
+
+        // a crucible that starts on the goal doesn't need to move at all
+        if goal == (start.y, start.x) {
+            return Some((vec![goal], 0));
         }
 
-        vec![
-            self.step_in_direction(&self.direction.left(), grid),
-            self.step_in_direction(&self.direction.right(), grid),
-        ]
-        .into_iter()
-        .flatten()
-        .for_each(|p| successors.push(p));
+        let (stops, cost) = dijkstra(
+            &start,
+            |crucible| self.successors(crucible, spec),
+            |crucible| (crucible.y, crucible.x) == goal,
+        )?;
 
-        successors
-    }
+        let mut path = vec![(start.y, start.x)];
 
-    fn ultra_successors(&self, grid: &Grid) -> Vec<Pos> {
-        let mut successors = Vec::new();
+        // every stop after the start was reached by moving, so has a heading
+        for stop in &stops[1..] {
+            let Some(heading) = stop.heading else {
+                continue;
+            };
 
-        if self.steps_taken_in_direction < 10 {
-            if let Some(p) = self.step_in_direction(&self.direction, grid) {
-                successors.push(p);
+            while *path.last().unwrap() != (stop.y, stop.x) {
+                path.push(self.step(*path.last().unwrap(), heading).unwrap());
             }
         }
 
-        if self.steps_taken_in_direction >= 4 {
-            vec![
-                self.step_in_direction(&self.direction.left(), grid),
-                self.step_in_direction(&self.direction.right(), grid),
-            ]
-            .into_iter()
-            .flatten()
-            .for_each(|p| successors.push(p));
-        }
-
-        successors
+        Some((path, cost))
     }
 }
 
-impl Grid {
-    fn minimal_heat_loss(&self) -> usize {
-        let initial_pos = Pos::new(0, 0, Direction::Right);
-
-        let (_, cost) = dijkstra(
-            &initial_pos,
-            |pos| {
-                pos.successors(self)
-                    .into_iter()
-                    .map(|p| (p, self.data[p.y as usize][p.x as usize]))
-            },
-            |pos| pos.x == (self.width as isize) - 1 && pos.y == (self.height as isize) - 1,
-        )
-        .unwrap();
-
-        cost
-    }
+/// The path taken by the ordinary crucible is marked with arrows in the
+/// direction it travels.
+impl Render for Grid {
+    fn cells(&self) -> Vec<Vec<Cell>> {
+        let mut arrows = HashMap::new();
 
-    fn ultra_crucible_minimal_heat_loss(&self) -> usize {
-        let initial_pos = Pos {
-            y: 0,
-            x: 0,
-            direction: Direction::Right,
-            steps_taken_in_direction: 1,
-        };
+        if let Some((path, _)) = self.minimal_heat_loss(&CRUCIBLE) {
+            for pair in path.windows(2) {
+                let ((y0, x0), (y1, x1)) = (pair[0], pair[1]);
+                let arrow = if y1 > y0 {
+                    'v'
+                } else if y1 < y0 {
+                    '^'
+                } else if x1 > x0 {
+                    '>'
+                } else {
+                    '<'
+                };
+
+                arrows.insert((y1, x1), arrow);
+            }
+        }
+
+        let rows = self.data.iter().enumerate().map(|(y, row)| {
+            let arrows = &arrows;
 
-        let (_, cost) = dijkstra(
-            &initial_pos,
-            |pos| {
-                pos.ultra_successors(self)
-                    .into_iter()
-                    .map(|p| (p, self.data[p.y as usize][p.x as usize]))
-            },
-            |pos| {
-                pos.x == (self.width as isize) - 1
-                    && pos.y == (self.height as isize) - 1
-                    && pos.steps_taken_in_direction >= 4
-            },
-        )
-        .unwrap();
-
-        cost
+            row.iter().enumerate().map(move |(x, heat_loss)| {
+                let digit = char::from_digit(*heat_loss as u32, 10).unwrap();
+
+                match arrows.get(&(y, x)) {
+                    Some(arrow) => Cell::highlighted(digit, Color::Red, *arrow),
+                    None => Cell::new(digit),
+                }
+            })
+        });
+
+        rows.map(Iterator::collect).collect()
     }
 }
 
@@ -194,11 +183,18 @@ impl FromStr for Grid {
         for line in s.lines() {
             let mut row = Vec::new();
             for c in line.chars() {
-                row.push(c.to_digit(10).unwrap() as usize);
+                row.push(c.to_digit(10).ok_or(())? as usize);
             }
             data.push(row);
         }
 
+        if data.is_empty()
+            || data[0].is_empty()
+            || data.iter().any(|row| row.len() != data[0].len())
+        {
+            return Err(());
+        }
+
         let width = data[0].len();
         let height = data.len();
 
@@ -210,17 +206,53 @@ impl FromStr for Grid {
     }
 }
 
+fn part1(grid: &Grid) -> solution::Result<usize> {
+    let (_, cost) = grid
+        .minimal_heat_loss(&CRUCIBLE)
+        .ok_or("the crucible can't reach the goal")?;
+
+    Ok(cost)
+}
+
+fn part2(grid: &Grid) -> solution::Result<usize> {
+    let (_, cost) = grid
+        .minimal_heat_loss(&ULTRA_CRUCIBLE)
+        .ok_or("the ultra crucible can't reach the goal")?;
+
+    Ok(cost)
+}
+
+pub struct Day17;
+
+impl Solution for Day17 {
+    const DAY: u32 = 17;
+
+    type Input = Grid;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input.parse().map_err(|_| "invalid heat loss map".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input)?.into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input)?.into())
+    }
+}
+
 #[aoc_generator(day17)]
-fn parse_input(input: &str) -> Grid {
-    input.parse().unwrap()
+fn generator(input: &str) -> solution::Result<<Day17 as Solution>::Input> {
+    Day17::parse(input)
 }
 
 #[aoc(day17, part1)]
-fn part1(grid: &Grid) -> usize {
-    grid.minimal_heat_loss()
+fn run_part1(input: &<Day17 as Solution>::Input) -> solution::Result<Answer> {
+    Day17::part1(input)
 }
 
 #[aoc(day17, part2)]
-fn part2(grid: &Grid) -> usize {
-    grid.ultra_crucible_minimal_heat_loss()
+fn run_part2(input: &<Day17 as Solution>::Input) -> solution::Result<Answer> {
+    Day17::part2(input)
 }
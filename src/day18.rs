@@ -1,3 +1,4 @@
+use crate::solution::{self, Answer, Solution};
 use crate::util::Direction;
 use aoc_runner_derive::{aoc, aoc_generator};
 use pathfinding::prelude::{component_index, connected_components};
@@ -16,16 +17,19 @@ impl FromStr for DigOp {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let toks = s.split_whitespace().collect::<Vec<_>>();
+        let [direction, distance, color] = toks[..] else {
+            return Err(());
+        };
 
-        let direction = match toks[0] {
+        let direction = match direction {
             "U" => Direction::Up,
             "D" => Direction::Down,
             "L" => Direction::Left,
             "R" => Direction::Right,
             _ => return Err(()),
         };
-        let distance = toks[1].parse().map_err(|_| ())?;
-        let color = toks[2]
+        let distance = distance.parse().map_err(|_| ())?;
+        let color = color
             .strip_prefix("(#")
             .ok_or(())?
             .strip_suffix(")")
@@ -46,7 +50,7 @@ struct Hole {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct DigMap {
+pub struct DigMap {
     ops: Vec<DigOp>,
     map: HashMap<(isize, isize), Hole>,
 }
@@ -123,6 +127,29 @@ impl DigMap {
         total_cell_count - outer_comp.len()
     }
 
+    /// Area of the lagoon from the trench's corners alone: the shoelace
+    /// formula gives the interior, and Pick's theorem adds the trench itself.
+    pub fn lagoon_area(&self) -> usize {
+        let mut pos = (0isize, 0isize);
+        let mut twice_area = 0isize;
+        let mut perimeter = 0isize;
+
+        for op in &self.ops {
+            let next = match op.direction {
+                Direction::Up => (pos.0, pos.1 + op.distance),
+                Direction::Down => (pos.0, pos.1 - op.distance),
+                Direction::Left => (pos.0 - op.distance, pos.1),
+                Direction::Right => (pos.0 + op.distance, pos.1),
+            };
+
+            twice_area += pos.0 * next.1 - next.0 * pos.1;
+            perimeter += op.distance;
+            pos = next;
+        }
+
+        (twice_area.unsigned_abs() + perimeter as usize) / 2 + 1
+    }
+
     fn grid_bounds(&self) -> ((isize, isize), (isize, isize)) {
         let mut min_x = isize::MAX;
         let mut max_x = isize::MIN;
@@ -148,15 +175,35 @@ impl DigMap {
     }
 }
 
-#[aoc_generator(day18)]
-fn parse_input(input: &str) -> DigMap {
-    input.parse().unwrap()
-}
-
-#[aoc(day18, part1)]
 fn part1(dig_map: &DigMap) -> usize {
     let mut dig_map = dig_map.clone();
     dig_map.dig_trench();
 
     dig_map.dig_size()
 }
+
+pub struct Day18;
+
+impl Solution for Day18 {
+    const DAY: u32 = 18;
+
+    type Input = DigMap;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input.parse().map_err(|_| "invalid dig plan".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+}
+
+#[aoc_generator(day18)]
+fn generator(input: &str) -> solution::Result<<Day18 as Solution>::Input> {
+    Day18::parse(input)
+}
+
+#[aoc(day18, part1)]
+fn run_part1(input: &<Day18 as Solution>::Input) -> solution::Result<Answer> {
+    Day18::part1(input)
+}
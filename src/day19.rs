@@ -1,63 +1,639 @@
+use crate::dot::{Digraph, ToDot};
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
-use lazy_static::lazy_static;
-use regex::Regex;
-use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
 use std::str::FromStr;
 
-lazy_static! {
-    static ref RE_PART: Regex = Regex::new(r"x=(\d+),m=(\d+),a=(\d+),s=(\d+)").unwrap();
-    static ref RE_RULE: Regex = Regex::new(r"([A-Za-z])([<>=])(\d+):(\w+)").unwrap();
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleError {
+    InvalidCharacter(char),
+    NumberTooLarge(String),
+    /// Something other than what the grammar expected, or `None` at the end
+    /// of the line.
+    Unexpected {
+        found: Option<String>,
+        expected: &'static str,
+    },
+    DuplicateRating(String),
+    /// A part that doesn't rate the same fields as the first part.
+    MismatchedFields,
+    /// A rule that tests a field no part is rated on.
+    UnknownField(String),
+    MissingParts,
+    /// More than [`MAX_NESTING`] parentheses open at once.
+    TooDeeplyNested,
+    /// A line of more than [`MAX_TOKENS`] tokens.
+    TooLong,
+    DuplicateWorkflow(String),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Part {
-    x: usize,
-    m: usize,
-    a: usize,
-    s: usize,
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::InvalidCharacter(c) => write!(f, "invalid character {:?}", c),
+            RuleError::NumberTooLarge(n) => write!(f, "{} is too large", n),
+            RuleError::Unexpected { found, expected } => {
+                match SYMBOLS.contains(expected) {
+                    true => write!(f, "expected `{}`, ", expected)?,
+                    false => write!(f, "expected {}, ", expected)?,
+                }
+
+                match found {
+                    Some(found) => write!(f, "found {:?}", found),
+                    None => write!(f, "found the end of the line"),
+                }
+            }
+            RuleError::DuplicateRating(field) => write!(f, "{} is rated more than once", field),
+            RuleError::MismatchedFields => {
+                write!(f, "parts don't all rate the same fields")
+            }
+            RuleError::UnknownField(field) => write!(f, "no part is rated on {}", field),
+            RuleError::MissingParts => write!(f, "expected a blank line before the parts"),
+            RuleError::TooDeeplyNested => {
+                write!(f, "more than {} parentheses are open at once", MAX_NESTING)
+            }
+            RuleError::TooLong => write!(f, "a line has more than {} tokens", MAX_TOKENS),
+            RuleError::DuplicateWorkflow(name) => write!(f, "{} is defined more than once", name),
+        }
+    }
 }
 
-impl Part {
-    fn rating_number(&self) -> usize {
-        self.x + self.m + self.a + self.s
+impl Error for RuleError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalError {
+    Overflow,
+    /// A part that comes back to a workflow it already went through, and so
+    /// would go round forever.
+    Cycle,
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::Overflow => write!(f, "arithmetic overflow in a rule"),
+            EvalError::Cycle => write!(f, "a part goes round the workflows forever"),
+        }
     }
 }
 
-impl FromStr for Part {
-    type Err = ();
+impl Error for EvalError {}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let caps = RE_PART.captures(s).ok_or(())?;
-        let x = caps.get(1).unwrap().as_str().parse().map_err(|_| ())?;
-        let m = caps.get(2).unwrap().as_str().parse().map_err(|_| ())?;
-        let a = caps.get(3).unwrap().as_str().parse().map_err(|_| ())?;
-        let s = caps.get(4).unwrap().as_str().parse().map_err(|_| ())?;
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Number(i64),
+    Symbol(&'static str),
+}
 
-        Ok(Self { x, m, a, s })
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "{}", name),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+/// Longer symbols come first, so that `<=` isn't read as `<` then `=`.
+const SYMBOLS: [&str; 18] = [
+    "<=", ">=", "!=", "==", "&&", "||", "<", ">", "=", "+", "-", "*", "(", ")", ":", ",", "{", "}",
+];
+
+/// Splits a line into words, numbers and symbols. A word is a run of
+/// letters, digits and underscores; one of only digits is a number.
+fn tokenize(s: &str) -> Result<Vec<Token>, RuleError> {
+    let mut tokens = vec![];
+    let mut rest = s;
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c.is_ascii_alphanumeric() || c == '_' {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            let (word, after) = rest.split_at(len);
+
+            tokens.push(if word.bytes().all(|b| b.is_ascii_digit()) {
+                Token::Number(
+                    word.parse()
+                        .map_err(|_| RuleError::NumberTooLarge(word.to_string()))?,
+                )
+            } else {
+                Token::Ident(word.to_string())
+            });
+            rest = after;
+        } else {
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(**symbol))
+                .ok_or(RuleError::InvalidCharacter(c))?;
+
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Arithmetic on a part's ratings.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Expr {
+    Number(i64),
+    Field(String),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// Evaluates the expression with `rating` giving the value of each
+    /// field.
+    pub fn evaluate(&self, rating: &impl Fn(&str) -> i64) -> Result<i64, EvalError> {
+        let (l, r, op): (_, _, fn(i64, i64) -> Option<i64>) = match self {
+            Expr::Number(n) => return Ok(*n),
+            Expr::Field(field) => return Ok(rating(field)),
+            Expr::Add(l, r) => (l, r, i64::checked_add),
+            Expr::Sub(l, r) => (l, r, i64::checked_sub),
+            Expr::Mul(l, r) => (l, r, i64::checked_mul),
+        };
+
+        op(l.evaluate(rating)?, r.evaluate(rating)?).ok_or(EvalError::Overflow)
+    }
+
+    fn fields<'a>(&'a self, fields: &mut Vec<&'a str>) {
+        match self {
+            Expr::Number(_) => (),
+            Expr::Field(field) => fields.push(field),
+            Expr::Add(l, r) | Expr::Sub(l, r) | Expr::Mul(l, r) => {
+                l.fields(fields);
+                r.fields(fields);
+            }
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Add(..) | Expr::Sub(..) => 1,
+            Expr::Mul(..) => 2,
+            Expr::Number(_) | Expr::Field(_) => 3,
+        }
+    }
+
+    /// Writes the expression, in parentheses if it binds less tightly than
+    /// `min`. Operators group to the left, so a right operand of the same
+    /// precedence is bracketed.
+    fn write(&self, f: &mut fmt::Formatter<'_>, min: u8) -> fmt::Result {
+        let (l, op, r) = match self {
+            Expr::Number(n) => return write!(f, "{}", n),
+            Expr::Field(field) => return write!(f, "{}", field),
+            Expr::Add(l, r) => (l, '+', r),
+            Expr::Sub(l, r) => (l, '-', r),
+            Expr::Mul(l, r) => (l, '*', r),
+        };
+
+        let precedence = self.precedence();
+        if precedence < min {
+            write!(f, "(")?;
+        }
+
+        l.write(f, precedence)?;
+        write!(f, "{}", op)?;
+        r.write(f, precedence + 1)?;
+
+        if precedence < min {
+            write!(f, ")")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Var {
-    X,
-    M,
-    A,
-    S,
+pub enum Comparison {
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+    GreaterEqual,
+    Greater,
 }
 
-impl FromStr for Var {
-    type Err = ();
+impl Comparison {
+    fn holds(self, l: i64, r: i64) -> bool {
+        match self {
+            Comparison::Less => l < r,
+            Comparison::LessEqual => l <= r,
+            Comparison::Equal => l == r,
+            Comparison::NotEqual => l != r,
+            Comparison::GreaterEqual => l >= r,
+            Comparison::Greater => l > r,
+        }
+    }
+
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol {
+            "<" => Some(Comparison::Less),
+            "<=" => Some(Comparison::LessEqual),
+            "=" | "==" => Some(Comparison::Equal),
+            "!=" => Some(Comparison::NotEqual),
+            ">=" => Some(Comparison::GreaterEqual),
+            ">" => Some(Comparison::Greater),
+            _ => None,
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Less => "<",
+            Comparison::LessEqual => "<=",
+            Comparison::Equal => "=",
+            Comparison::NotEqual => "!=",
+            Comparison::GreaterEqual => ">=",
+            Comparison::Greater => ">",
+        }
+    }
+}
+
+/// The test a rule makes of a part.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Condition {
+    Compare(Expr, Comparison, Expr),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl Condition {
+    /// Evaluates the condition with `rating` giving the value of each
+    /// field. `&&` and `||` only evaluate their right side when they need
+    /// to.
+    pub fn evaluate(&self, rating: &impl Fn(&str) -> i64) -> Result<bool, EvalError> {
+        match self {
+            Condition::Compare(l, cmp, r) => {
+                Ok(cmp.holds(l.evaluate(rating)?, r.evaluate(rating)?))
+            }
+            Condition::And(l, r) => Ok(l.evaluate(rating)? && r.evaluate(rating)?),
+            Condition::Or(l, r) => Ok(l.evaluate(rating)? || r.evaluate(rating)?),
+        }
+    }
+
+    fn fields<'a>(&'a self, fields: &mut Vec<&'a str>) {
+        match self {
+            Condition::Compare(l, _, r) => {
+                l.fields(fields);
+                r.fields(fields);
+            }
+            Condition::And(l, r) | Condition::Or(l, r) => {
+                l.fields(fields);
+                r.fields(fields);
+            }
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Condition::Or(..) => 1,
+            Condition::And(..) => 2,
+            Condition::Compare(..) => 3,
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, min: u8) -> fmt::Result {
+        let (l, op, r) = match self {
+            Condition::Compare(l, cmp, r) => return write!(f, "{}{}{}", l, cmp.symbol(), r),
+            Condition::And(l, r) => (l, "&&", r),
+            Condition::Or(l, r) => (l, "||", r),
+        };
+
+        let precedence = self.precedence();
+        if precedence < min {
+            write!(f, "(")?;
+        }
+
+        l.write(f, precedence)?;
+        write!(f, " {} ", op)?;
+        r.write(f, precedence + 1)?;
+
+        if precedence < min {
+            write!(f, ")")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Prints with as few parentheses as parse back to the same condition, and
+/// a rule from the original puzzle prints just as it was written.
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+impl FromStr for Condition {
+    type Err = RuleError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "x" => Ok(Self::X),
-            "m" => Ok(Self::M),
-            "a" => Ok(Self::A),
-            "s" => Ok(Self::S),
-            _ => Err(()),
+        let mut parser = Parser::new(s)?;
+        let condition = parser.condition()?;
+        parser.end()?;
+
+        Ok(condition)
+    }
+}
+
+/// A recursive-descent parser over one line's tokens:
+///
+/// ```text
+/// workflow   = name "{" (condition ":" name ",")* name "}"
+/// part       = "{" (name "=" "-"? number),* "}"
+/// condition  = conjunction ("||" conjunction)*
+/// conjunction = comparison ("&&" comparison)*
+/// comparison = expr ("<" | "<=" | "=" | "==" | "!=" | ">=" | ">") expr
+///            | "(" condition ")"
+/// expr       = term (("+" | "-") term)*
+/// term       = factor ("*" factor)*
+/// factor     = number | name | "(" expr ")"
+/// ```
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// How many parentheses are open.
+    depth: usize,
+}
+
+/// The parser and the syntax trees it builds recurse once for each pair of
+/// parentheses, so deeper nesting is refused rather than risking the stack.
+pub const MAX_NESTING: usize = 64;
+
+/// Bounding a line's length bounds its longest chain of operators, and so
+/// how deep the syntax trees built from it can grow.
+pub const MAX_TOKENS: usize = 4096;
+
+impl Parser {
+    fn new(s: &str) -> Result<Self, RuleError> {
+        let tokens = tokenize(s)?;
+
+        if tokens.len() > MAX_TOKENS {
+            return Err(RuleError::TooLong);
+        }
+
+        Ok(Self {
+            tokens,
+            pos: 0,
+            depth: 0,
+        })
+    }
+
+    /// Parses what `parse` does inside a pair of parentheses, the opening
+    /// one already consumed.
+    fn parenthesised<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, RuleError>,
+    ) -> Result<T, RuleError> {
+        if self.depth == MAX_NESTING {
+            return Err(RuleError::TooDeeplyNested);
+        }
+
+        self.depth += 1;
+        let inner = parse(self).and_then(|inner| {
+            self.expect(")")?;
+            Ok(inner)
+        });
+        self.depth -= 1;
+
+        inner
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn unexpected<T>(&self, expected: &'static str) -> Result<T, RuleError> {
+        Err(RuleError::Unexpected {
+            found: self.peek().map(|t| t.to_string()),
+            expected,
+        })
+    }
+
+    /// Consumes `symbol` if it comes next.
+    fn eat(&mut self, symbol: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol);
+        if found {
+            self.pos += 1;
+        }
+
+        found
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<(), RuleError> {
+        match self.eat(symbol) {
+            true => Ok(()),
+            false => self.unexpected(symbol),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, RuleError> {
+        match self.peek() {
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => self.unexpected("a name"),
+        }
+    }
+
+    fn number(&mut self) -> Result<i64, RuleError> {
+        match self.peek() {
+            Some(Token::Number(n)) => {
+                let n = *n;
+                self.pos += 1;
+                Ok(n)
+            }
+            _ => self.unexpected("a number"),
+        }
+    }
+
+    fn end(&self) -> Result<(), RuleError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => self.unexpected("the end of the line"),
+        }
+    }
+
+    fn workflow(&mut self) -> Result<Workflow, RuleError> {
+        let name = self.name()?;
+        self.expect("{")?;
+
+        let mut rules = vec![];
+
+        // the fallback is the only rule that is a lone name before the `}`
+        let fallback = loop {
+            if let [Token::Ident(_), Token::Symbol("}"), ..] = &self.tokens[self.pos..] {
+                break self.name()?.parse::<Dest>().unwrap();
+            }
+
+            let condition = self.condition()?;
+            self.expect(":")?;
+            let dest = self.name()?.parse::<Dest>().unwrap();
+            self.expect(",")?;
+
+            rules.push(Rule { condition, dest });
+        };
+
+        self.expect("}")?;
+        self.end()?;
+
+        Ok(Workflow {
+            name,
+            rules,
+            fallback,
+        })
+    }
+
+    fn ratings(&mut self) -> Result<Vec<(String, i64)>, RuleError> {
+        self.expect("{")?;
+
+        let mut ratings = vec![];
+
+        loop {
+            let field = self.name()?;
+            self.expect("=")?;
+            let negative = self.eat("-");
+            let value = self.number()?;
+
+            ratings.push((field, if negative { -value } else { value }));
+
+            if !self.eat(",") {
+                break;
+            }
+        }
+
+        self.expect("}")?;
+        self.end()?;
+
+        Ok(ratings)
+    }
+
+    fn condition(&mut self) -> Result<Condition, RuleError> {
+        let mut condition = self.conjunction()?;
+
+        while self.eat("||") {
+            condition = Condition::Or(Box::new(condition), Box::new(self.conjunction()?));
+        }
+
+        Ok(condition)
+    }
+
+    fn conjunction(&mut self) -> Result<Condition, RuleError> {
+        let mut condition = self.comparison()?;
+
+        while self.eat("&&") {
+            condition = Condition::And(Box::new(condition), Box::new(self.comparison()?));
+        }
+
+        Ok(condition)
+    }
+
+    /// A `(` may open either an expression, as in `(x+m)*2>a`, or a whole
+    /// condition, as in `(x>1||m>1)&&a>1`; the first is tried first.
+    fn comparison(&mut self) -> Result<Condition, RuleError> {
+        let start = self.pos;
+
+        let compare = (|| {
+            let l = self.expr()?;
+            let cmp = match self.peek() {
+                Some(Token::Symbol(s)) => Comparison::from_symbol(s),
+                _ => None,
+            };
+            let Some(cmp) = cmp else {
+                return self.unexpected("a comparison");
+            };
+            self.pos += 1;
+
+            Ok(Condition::Compare(l, cmp, self.expr()?))
+        })();
+
+        match compare {
+            Err(_) if self.tokens.get(start) == Some(&Token::Symbol("(")) => {
+                self.pos = start + 1;
+                self.parenthesised(Self::condition)
+            }
+            compare => compare,
         }
     }
+
+    fn expr(&mut self) -> Result<Expr, RuleError> {
+        let mut expr = self.term()?;
+
+        loop {
+            if self.eat("+") {
+                expr = Expr::Add(Box::new(expr), Box::new(self.term()?));
+            } else if self.eat("-") {
+                expr = Expr::Sub(Box::new(expr), Box::new(self.term()?));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, RuleError> {
+        let mut expr = self.factor()?;
+
+        while self.eat("*") {
+            expr = Expr::Mul(Box::new(expr), Box::new(self.factor()?));
+        }
+
+        Ok(expr)
+    }
+
+    fn factor(&mut self) -> Result<Expr, RuleError> {
+        match self.peek() {
+            Some(Token::Number(_)) => Ok(Expr::Number(self.number()?)),
+            Some(Token::Ident(_)) => Ok(Expr::Field(self.name()?)),
+            Some(Token::Symbol("(")) => {
+                self.pos += 1;
+                self.parenthesised(Self::expr)
+            }
+            _ => self.unexpected("a number, a field or `(`"),
+        }
+    }
+}
+
+/// A part's ratings, in the order of its system's fields.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Part {
+    ratings: Vec<i64>,
+}
+
+impl Part {
+    pub fn new(ratings: Vec<i64>) -> Self {
+        Self { ratings }
+    }
+
+    pub fn ratings(&self) -> &[i64] {
+        &self.ratings
+    }
+
+    /// The sum of the part's ratings. It only has to fit at the end, since
+    /// ratings of either sign can overflow part way through.
+    pub fn rating_number(&self) -> Result<i64, EvalError> {
+        let sum = self.ratings.iter().map(|&r| i128::from(r)).sum::<i128>();
+
+        i64::try_from(sum).map_err(|_| EvalError::Overflow)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -81,30 +657,10 @@ impl FromStr for Dest {
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Rule {
-    var: Var,
-    op: Ordering,
-    val: usize,
+    condition: Condition,
     dest: Dest,
 }
 
-impl FromStr for Rule {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let caps = RE_RULE.captures(s).ok_or(())?;
-        let var = caps.get(1).unwrap().as_str().parse()?;
-        let op = match caps.get(2).unwrap().as_str() {
-            "<" => Ordering::Less,
-            "=" => Ordering::Equal,
-            ">" => Ordering::Greater,
-            _ => return Err(()),
-        };
-        let val = caps.get(3).unwrap().as_str().parse().map_err(|_| ())?;
-        let dest = caps.get(4).unwrap().as_str().parse()?;
-        Ok(Self { var, op, val, dest })
-    }
-}
-
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Workflow {
     name: String,
@@ -113,128 +669,551 @@ struct Workflow {
 }
 
 impl Workflow {
-    fn run(&self, part: &Part) -> &Dest {
+    fn run(&self, rating: &impl Fn(&str) -> i64) -> Result<&Dest, EvalError> {
         for rule in &self.rules {
-            let val = match rule.var {
-                Var::X => part.x,
-                Var::M => part.m,
-                Var::A => part.a,
-                Var::S => part.s,
-            };
-            if val.cmp(&rule.val) == rule.op {
-                return &rule.dest;
+            if rule.condition.evaluate(rating)? {
+                return Ok(&rule.dest);
             }
         }
-        &self.fallback
+
+        Ok(&self.fallback)
     }
 }
 
 impl FromStr for Workflow {
-    type Err = ();
+    type Err = RuleError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (name, rest) = s.split_once("{").ok_or(())?;
-        let rules_s = rest.strip_suffix("}").ok_or(())?;
-        let rules_strings = rules_s.split(",").collect::<Vec<_>>();
-        let (rules_strings, fallback) = (
-            &rules_strings[..rules_strings.len() - 1],
-            rules_strings[rules_strings.len() - 1],
-        );
-
-        let rules = rules_strings
-            .iter()
-            .map(|s| s.parse())
-            .collect::<Result<Vec<_>, _>>()?;
+        Parser::new(s)?.workflow()
+    }
+}
 
-        Ok(Self {
-            name: name.to_string(),
-            rules,
-            fallback: fallback.parse()?,
-        })
+impl Dest {
+    fn node(&self) -> &str {
+        match self {
+            Dest::Accept => "A",
+            Dest::Reject => "R",
+            Dest::Workflow(name) => name,
+        }
     }
 }
 
-struct System {
+pub struct System {
+    fields: Vec<String>,
     parts: Vec<Part>,
     workflows: HashMap<String, Workflow>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct RunResult {
-    accepted: Vec<Part>,
-    rejected: Vec<Part>,
+/// Where a compiled program sends a part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Accept,
+    Reject,
+    /// The instruction a workflow starts at.
+    Jump(usize),
+    /// A workflow that doesn't exist, where parts are neither accepted
+    /// nor rejected.
+    Missing,
+}
+
+/// One step of a compiled program, which works on a stack of numbers.
+/// Branches jump to `to` when their comparison's result is `when`, and
+/// otherwise go on to the next instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Instruction {
+    /// Compares a rating with a number without touching the stack, which is
+    /// all a rule from the original puzzle needs.
+    BranchField {
+        field: usize,
+        cmp: Comparison,
+        val: i64,
+        when: bool,
+        to: usize,
+    },
+    Field(usize),
+    Number(i64),
+    Add,
+    Sub,
+    Mul,
+    /// Compares the top two numbers, popping them.
+    Branch {
+        cmp: Comparison,
+        when: bool,
+        to: usize,
+    },
+    Send(Target),
+}
+
+/// Workflows compiled into one flat list of instructions, with every
+/// workflow name resolved to the index of its first instruction and every
+/// field to the index of its rating.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    instructions: Vec<Instruction>,
+    start: Target,
+    /// How many workflows a part can go through without repeating one.
+    workflows: usize,
+}
+
+/// Builds a program, with jumps first pointing at labels that are resolved
+/// to instruction indices once every label has been placed.
+struct Compiler<'a> {
+    instructions: Vec<Instruction>,
+    labels: Vec<usize>,
+    fields: &'a [String],
+    starts: HashMap<&'a str, usize>,
+}
+
+impl Compiler<'_> {
+    fn label(&mut self) -> usize {
+        self.labels.push(usize::MAX);
+        self.labels.len() - 1
+    }
+
+    fn place(&mut self, label: usize) {
+        self.labels[label] = self.instructions.len();
+    }
+
+    fn target(&self, dest: &Dest) -> Target {
+        match dest {
+            Dest::Accept => Target::Accept,
+            Dest::Reject => Target::Reject,
+            Dest::Workflow(name) => self
+                .starts
+                .get(name.as_str())
+                .map_or(Target::Missing, |&label| Target::Jump(label)),
+        }
+    }
+
+    fn field(&self, field: &str) -> usize {
+        self.fields.iter().position(|f| f == field).unwrap()
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        let (l, r, op) = match expr {
+            Expr::Number(n) => return self.instructions.push(Instruction::Number(*n)),
+            Expr::Field(field) => {
+                let field = self.field(field);
+                return self.instructions.push(Instruction::Field(field));
+            }
+            Expr::Add(l, r) => (l, r, Instruction::Add),
+            Expr::Sub(l, r) => (l, r, Instruction::Sub),
+            Expr::Mul(l, r) => (l, r, Instruction::Mul),
+        };
+
+        self.expr(l);
+        self.expr(r);
+        self.instructions.push(op);
+    }
+
+    /// Jumps to `to` if the condition comes out as `when`, and falls through
+    /// otherwise.
+    fn branch(&mut self, condition: &Condition, when: bool, to: usize) {
+        match condition {
+            Condition::Compare(Expr::Field(field), cmp, Expr::Number(val)) => {
+                let field = self.field(field);
+                self.instructions.push(Instruction::BranchField {
+                    field,
+                    cmp: *cmp,
+                    val: *val,
+                    when,
+                    to,
+                });
+            }
+            Condition::Compare(l, cmp, r) => {
+                self.expr(l);
+                self.expr(r);
+                self.instructions.push(Instruction::Branch {
+                    cmp: *cmp,
+                    when,
+                    to,
+                });
+            }
+            // `&&` jumps as soon as one side is false; `||` as soon as one
+            // side is true
+            Condition::And(l, r) | Condition::Or(l, r) => {
+                let short_circuit = matches!(condition, Condition::Or(..));
+
+                if when == short_circuit {
+                    self.branch(l, when, to);
+                    self.branch(r, when, to);
+                } else {
+                    let skip = self.label();
+                    self.branch(l, short_circuit, skip);
+                    self.branch(r, when, to);
+                    self.place(skip);
+                }
+            }
+        }
+    }
+
+    fn workflow(&mut self, workflow: &Workflow) {
+        self.place(self.starts[workflow.name.as_str()]);
+
+        for rule in &workflow.rules {
+            let next = self.label();
+            self.branch(&rule.condition, false, next);
+            self.instructions
+                .push(Instruction::Send(self.target(&rule.dest)));
+            self.place(next);
+        }
+
+        self.instructions
+            .push(Instruction::Send(self.target(&workflow.fallback)));
+    }
+
+    fn finish(self, start: Target, workflows: usize) -> Program {
+        let labels = self.labels;
+        let resolve = |target| match target {
+            Target::Jump(label) => Target::Jump(labels[label]),
+            target => target,
+        };
+
+        let instructions = self
+            .instructions
+            .into_iter()
+            .map(|instruction| match instruction {
+                Instruction::BranchField {
+                    field,
+                    cmp,
+                    val,
+                    when,
+                    to,
+                } => Instruction::BranchField {
+                    field,
+                    cmp,
+                    val,
+                    when,
+                    to: labels[to],
+                },
+                Instruction::Branch { cmp, when, to } => Instruction::Branch {
+                    cmp,
+                    when,
+                    to: labels[to],
+                },
+                Instruction::Send(target) => Instruction::Send(resolve(target)),
+                instruction => instruction,
+            })
+            .collect();
+
+        Program {
+            instructions,
+            start: resolve(start),
+            workflows,
+        }
+    }
+}
+
+impl Program {
+    fn compile(fields: &[String], workflows: &HashMap<String, Workflow>) -> Self {
+        let mut names = workflows.keys().collect::<Vec<_>>();
+        names.sort();
+
+        let mut compiler = Compiler {
+            instructions: vec![],
+            labels: vec![],
+            fields,
+            starts: HashMap::new(),
+        };
+
+        for name in &names {
+            let label = compiler.label();
+            compiler.starts.insert(name.as_str(), label);
+        }
+
+        for name in &names {
+            compiler.workflow(&workflows[*name]);
+        }
+
+        let start = compiler.target(&Dest::Workflow("in".to_string()));
+
+        compiler.finish(start, names.len())
+    }
+
+    /// Whether the workflows accept `part`.
+    pub fn accepts(&self, part: &Part) -> Result<bool, EvalError> {
+        let mut pc = match self.start {
+            Target::Jump(start) => start,
+            target => return Ok(target == Target::Accept),
+        };
+        let mut stack = Vec::new();
+        let mut visited = 1;
+
+        let apply = |stack: &mut Vec<i64>, op: fn(i64, i64) -> Option<i64>| {
+            let (r, l) = (stack.pop().unwrap(), stack.pop().unwrap());
+            stack.push(op(l, r).ok_or(EvalError::Overflow)?);
+            Ok(())
+        };
+
+        loop {
+            pc = match self.instructions[pc] {
+                Instruction::BranchField {
+                    field,
+                    cmp,
+                    val,
+                    when,
+                    to,
+                } => match cmp.holds(part.ratings[field], val) == when {
+                    true => to,
+                    false => pc + 1,
+                },
+                Instruction::Field(field) => {
+                    stack.push(part.ratings[field]);
+                    pc + 1
+                }
+                Instruction::Number(n) => {
+                    stack.push(n);
+                    pc + 1
+                }
+                Instruction::Add => apply(&mut stack, i64::checked_add).map(|_| pc + 1)?,
+                Instruction::Sub => apply(&mut stack, i64::checked_sub).map(|_| pc + 1)?,
+                Instruction::Mul => apply(&mut stack, i64::checked_mul).map(|_| pc + 1)?,
+                Instruction::Branch { cmp, when, to } => {
+                    let (r, l) = (stack.pop().unwrap(), stack.pop().unwrap());
+
+                    match cmp.holds(l, r) == when {
+                        true => to,
+                        false => pc + 1,
+                    }
+                }
+                Instruction::Send(Target::Jump(start)) => {
+                    visited += 1;
+                    if visited > self.workflows {
+                        return Err(EvalError::Cycle);
+                    }
+
+                    start
+                }
+                Instruction::Send(target) => return Ok(target == Target::Accept),
+            };
+        }
+    }
+
+    /// The summed ratings of every accepted part.
+    pub fn accepted_ratings(&self, parts: &[Part]) -> Result<i64, EvalError> {
+        let mut total = 0i128;
+
+        for part in parts {
+            if self.accepts(part)? {
+                total += i128::from(part.rating_number()?);
+            }
+        }
+
+        i64::try_from(total).map_err(|_| EvalError::Overflow)
+    }
 }
 
-impl RunResult {
-    fn sum_rating_numbers(&self) -> usize {
-        self.accepted.iter().map(|p| p.rating_number()).sum()
+/// Workflows are boxes, with the `in` workflow in bold, and each rule is an
+/// edge labelled with its condition. Fallbacks are dashed, and parts end at
+/// the accept and reject sinks.
+impl ToDot for System {
+    fn to_dot(&self) -> String {
+        let mut graph = Digraph::new("workflows");
+        graph.node_defaults(&[("shape", "box")]);
+
+        graph.node("in", &[("style", "bold")]);
+        graph.node(
+            "A",
+            &[
+                ("shape", "doublecircle"),
+                ("style", "filled"),
+                ("fillcolor", "palegreen"),
+            ],
+        );
+        graph.node(
+            "R",
+            &[
+                ("shape", "doublecircle"),
+                ("style", "filled"),
+                ("fillcolor", "salmon"),
+            ],
+        );
+
+        let mut workflows = self.workflows.values().collect::<Vec<_>>();
+        workflows.sort_by(|a, b| a.name.cmp(&b.name));
+
+        for workflow in workflows {
+            for rule in &workflow.rules {
+                graph.edge(
+                    &workflow.name,
+                    rule.dest.node(),
+                    &[("label", &rule.condition.to_string())],
+                );
+            }
+
+            graph.edge(
+                &workflow.name,
+                workflow.fallback.node(),
+                &[("style", "dashed")],
+            );
+        }
+
+        graph.finish()
     }
 }
 
 impl System {
-    fn run_workflows(&self, part: &Part) -> &Dest {
+    /// The fields every part is rated on, in the order `Part::new` takes
+    /// them.
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    pub fn compile(&self) -> Program {
+        Program::compile(&self.fields, &self.workflows)
+    }
+
+    /// Whether the workflows accept `part`, found by walking the rules'
+    /// syntax trees rather than compiling them.
+    pub fn accepts(&self, part: &Part) -> Result<bool, EvalError> {
+        let rating = |field: &str| {
+            let index = self.fields.iter().position(|f| f == field).unwrap();
+            part.ratings[index]
+        };
+
+        Ok(self.run_workflows(&rating)? == Some(&Dest::Accept))
+    }
+
+    fn run_workflows(&self, rating: &impl Fn(&str) -> i64) -> Result<Option<&Dest>, EvalError> {
         let mut dest = None;
         let mut workflow = self.workflows.get("in");
 
-        while let Some(w) = workflow {
-            dest = Some(w.run(part));
+        // a part sent on to more workflows than there are has seen one twice
+        for _ in 0..self.workflows.len() {
+            let Some(w) = workflow else {
+                break;
+            };
+
+            dest = Some(w.run(rating)?);
             workflow = match dest {
                 Some(Dest::Workflow(ref name)) => self.workflows.get(name),
                 _ => None,
             };
         }
 
-        dest.unwrap()
-    }
-
-    fn run_all_workflows(&self) -> RunResult {
-        let mut accepted = vec![];
-        let mut rejected = vec![];
-
-        for part in &self.parts {
-            let dest = self.run_workflows(part);
-            match dest {
-                Dest::Accept => accepted.push(*part),
-                Dest::Reject => rejected.push(*part),
-                _ => (),
-            }
+        match workflow {
+            Some(_) => Err(EvalError::Cycle),
+            None => Ok(dest),
         }
-
-        RunResult { accepted, rejected }
     }
 }
 
 impl FromStr for System {
-    type Err = ();
+    type Err = RuleError;
 
+    /// Every part must rate the same fields, though in any order, and the
+    /// rules may only test those fields. Without any parts, the fields are
+    /// those the rules test, in the order they first appear.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (workflows_s, parts_s) = s.split_once("\n\n").ok_or(())?;
-
-        let parts = parts_s
-            .lines()
-            .map(|l| l.parse())
-            .collect::<Result<Vec<_>, _>>()?;
+        let (workflows_s, parts_s) = s.split_once("\n\n").ok_or(RuleError::MissingParts)?;
 
         let workflows = workflows_s
             .lines()
             .map(|l| l.parse())
             .collect::<Result<Vec<Workflow>, _>>()?;
 
-        let workflows = workflows
+        let ratings = parts_s
+            .lines()
+            .map(|l| Parser::new(l)?.ratings())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut tested = vec![];
+        for rule in workflows.iter().flat_map(|w| &w.rules) {
+            rule.condition.fields(&mut tested);
+        }
+
+        let fields = match ratings.first() {
+            Some(first) => first.iter().map(|(f, _)| f.clone()).collect::<Vec<_>>(),
+            None => {
+                let mut seen = HashSet::new();
+                tested
+                    .iter()
+                    .filter(|f| seen.insert(**f))
+                    .map(|f| f.to_string())
+                    .collect()
+            }
+        };
+
+        if let Some(unknown) = tested.iter().find(|f| !fields.iter().any(|g| g == *f)) {
+            return Err(RuleError::UnknownField(unknown.to_string()));
+        }
+
+        let index = fields
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (f.as_str(), i))
+            .collect::<HashMap<_, _>>();
+
+        let parts = ratings
             .into_iter()
-            .map(|w| (w.name.clone(), w))
-            .collect::<HashMap<String, Workflow>>();
+            .map(|part| {
+                let mut ratings = vec![None; fields.len()];
+
+                for (field, value) in part {
+                    let slot = ratings
+                        .get_mut(
+                            *index
+                                .get(field.as_str())
+                                .ok_or(RuleError::MismatchedFields)?,
+                        )
+                        .unwrap();
+
+                    if slot.replace(value).is_some() {
+                        return Err(RuleError::DuplicateRating(field));
+                    }
+                }
+
+                let ratings = ratings.into_iter().collect::<Option<Vec<_>>>();
+
+                Ok(Part::new(ratings.ok_or(RuleError::MismatchedFields)?))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut by_name = HashMap::new();
+        for workflow in workflows {
+            if let Some(earlier) = by_name.insert(workflow.name.clone(), workflow) {
+                return Err(RuleError::DuplicateWorkflow(earlier.name));
+            }
+        }
+
+        Ok(Self {
+            fields,
+            parts,
+            workflows: by_name,
+        })
+    }
+}
+
+fn part1(system: &System) -> Result<i64, EvalError> {
+    system.compile().accepted_ratings(&system.parts)
+}
+
+pub struct Day19;
+
+impl Solution for Day19 {
+    const DAY: u32 = 19;
+
+    type Input = System;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        Ok(input.parse::<System>()?)
+    }
 
-        Ok(Self { parts, workflows })
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input)?.into())
     }
 }
 
 #[aoc_generator(day19)]
-fn parse_input(input: &str) -> System {
-    input.parse().unwrap()
+fn generator(input: &str) -> solution::Result<<Day19 as Solution>::Input> {
+    Day19::parse(input)
 }
 
 #[aoc(day19, part1)]
-fn part1(system: &System) -> usize {
-    system.run_all_workflows().sum_rating_numbers()
+fn run_part1(input: &<Day19 as Solution>::Input) -> solution::Result<Answer> {
+    Day19::part1(input)
 }
@@ -1,9 +1,10 @@
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
 use lazy_static::lazy_static;
 use regex::Regex;
 use std::str::FromStr;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Hand {
     red: u32,
     green: u32,
@@ -11,6 +12,31 @@ pub struct Hand {
 }
 
 impl Hand {
+    pub fn red(&self) -> u32 {
+        self.red
+    }
+
+    pub fn green(&self) -> u32 {
+        self.green
+    }
+
+    pub fn blue(&self) -> u32 {
+        self.blue
+    }
+
+    fn total(&self) -> usize {
+        self.red as usize + self.green as usize + self.blue as usize
+    }
+
+    /// The larger count of each colour.
+    fn union(&self, other: &Hand) -> Hand {
+        Hand {
+            red: self.red.max(other.red),
+            green: self.green.max(other.green),
+            blue: self.blue.max(other.blue),
+        }
+    }
+
     fn is_valid(&self) -> bool {
         self.red <= 12 && self.green <= 13 && self.blue <= 14
     }
@@ -88,23 +114,164 @@ impl FromStr for Game {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (game_w_id, rest) = s.split_once(":").unwrap();
-        let id = game_w_id.split_once(" ").unwrap().1.parse::<u32>().unwrap();
+        let (game_w_id, rest) = s.split_once(":").ok_or("missing ':'")?;
+        let id = game_w_id
+            .split_once(" ")
+            .ok_or("missing game id")?
+            .1
+            .parse::<u32>()
+            .map_err(|_| "invalid game id")?;
 
         let hands = rest.split(";");
         Ok(Game {
             id,
-            hands: hands.map(|h| h.parse::<_>().unwrap()).collect::<Vec<_>>(),
+            hands: hands
+                .map(|h| h.parse::<_>())
+                .collect::<Result<Vec<_>, _>>()?,
         })
     }
 }
 
-#[aoc_generator(day2)]
-pub fn input_generator(input: &str) -> Vec<Game> {
-    input.lines().map(|s| s.parse::<Game>().unwrap()).collect()
+/// A game whose draws force the smallest consistent bag to be larger than
+/// every other game would need on its own.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outlier {
+    pub id: u32,
+    /// How many cubes of each colour the bag could lose without this game.
+    pub excess: Hand,
+}
+
+/// The smallest bag that could have produced every hand of every game.
+pub fn smallest_bag(games: &[Game]) -> Hand {
+    games
+        .iter()
+        .map(|g| g.minimum_hand())
+        .fold(Hand::default(), |bag, hand| bag.union(&hand))
+}
+
+/// The games that alone need more of some colour than any other game.
+pub fn outliers(games: &[Game]) -> Vec<Outlier> {
+    let minimums = games.iter().map(|g| g.minimum_hand()).collect::<Vec<_>>();
+
+    // how far each colour's largest count stands above the runner-up
+    let excess_of = |count: fn(&Hand) -> u32, i: usize| {
+        let others = minimums
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, hand)| count(hand))
+            .max()
+            .unwrap_or(0);
+
+        count(&minimums[i]).saturating_sub(others)
+    };
+
+    games
+        .iter()
+        .enumerate()
+        .map(|(i, game)| Outlier {
+            id: game.id,
+            excess: Hand {
+                red: excess_of(Hand::red, i),
+                green: excess_of(Hand::green, i),
+                blue: excess_of(Hand::blue, i),
+            },
+        })
+        .filter(|outlier| outlier.excess != Hand::default())
+        .collect()
+}
+
+fn ln_factorials(n: usize) -> Vec<f64> {
+    let mut table = vec![0.0; n + 1];
+
+    for i in 1..table.len() {
+        table[i] = table[i - 1] + (i as f64).ln();
+    }
+
+    table
+}
+
+/// The bag that makes the observed hands most likely, if each hand is drawn
+/// without replacement from the full bag, among bags with at most `limit`
+/// cubes of each colour. Returns the bag and its log-likelihood, or `None`
+/// if no bag in range could have produced every hand.
+///
+/// Under this model a hand's probability is multivariate hypergeometric:
+/// `C(R, r) * C(G, g) * C(B, b) / C(R + G + B, r + g + b)`. The numerator
+/// splits by colour and the denominator depends only on the bag's total.
+/// Each colour's summed log-numerator is concave in its count, so the best
+/// bag of each size grows out of the smallest bag one cube at a time, each
+/// time adding the colour that gains the most. That leaves one candidate
+/// per bag size to compare, rather than one per bag.
+///
+/// A colour that comes back at `limit` was still getting more likely there:
+/// the hands are better explained by ever larger bags in those proportions
+/// than by any bag in range.
+pub fn most_likely_bag(games: &[Game], limit: u32) -> Option<(Hand, f64)> {
+    let smallest = smallest_bag(games);
+    let hands = games.iter().flat_map(|g| &g.hands).collect::<Vec<_>>();
+
+    let least = [smallest.red, smallest.green, smallest.blue].map(|c| c as usize);
+    let limit = limit as usize;
+
+    if least.iter().any(|&c| c > limit) {
+        return None;
+    }
+
+    let ln_fact = ln_factorials(3 * limit);
+    let ln_choose = |n: usize, k: usize| match k <= n {
+        true => ln_fact[n] - ln_fact[k] - ln_fact[n - k],
+        false => f64::NEG_INFINITY,
+    };
+
+    // summed log-numerator for each count of one colour
+    let colours: [fn(&Hand) -> u32; 3] = [Hand::red, Hand::green, Hand::blue];
+    let numerators = colours.map(|count| {
+        (0..=limit)
+            .map(|n| hands.iter().map(|h| ln_choose(n, count(h) as usize)).sum())
+            .collect::<Vec<f64>>()
+    });
+    let denominator = |total: usize| {
+        hands
+            .iter()
+            .map(|h| ln_choose(total, h.total()))
+            .sum::<f64>()
+    };
+
+    let likelihood = |bag: &[usize; 3]| {
+        (0..3).map(|c| numerators[c][bag[c]]).sum::<f64>() - denominator(bag.iter().sum())
+    };
+    let gain = |bag: &[usize; 3], c: usize| numerators[c][bag[c] + 1] - numerators[c][bag[c]];
+
+    let mut bag = least;
+    let mut best = (bag, likelihood(&bag));
+
+    while let Some(colour) = (0..3)
+        .filter(|&c| bag[c] < limit)
+        .max_by(|&a, &b| gain(&bag, a).total_cmp(&gain(&bag, b)))
+    {
+        bag[colour] += 1;
+
+        let ll = likelihood(&bag);
+        if ll > best.1 {
+            best = (bag, ll);
+        }
+    }
+
+    let ([red, green, blue], ll) = best;
+    let bag = Hand {
+        red: red as u32,
+        green: green as u32,
+        blue: blue as u32,
+    };
+
+    Some((bag, ll))
+}
+
+pub fn input_generator(input: &str) -> Result<Vec<Game>, &'static str> {
+    input.lines().map(|s| s.parse::<Game>()).collect()
 }
 
-#[aoc(day2, part1)]
 pub fn part1(games: &[Game]) -> u32 {
     games
         .iter()
@@ -112,7 +279,41 @@ pub fn part1(games: &[Game]) -> u32 {
         .sum()
 }
 
-#[aoc(day2, part2)]
 pub fn part2(games: &[Game]) -> u32 {
     games.iter().map(|g| g.minimum_hand().power()).sum()
 }
+
+pub struct Day2;
+
+impl Solution for Day2 {
+    const DAY: u32 = 2;
+
+    type Input = Vec<Game>;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input_generator(input).map_err(|e| e.into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
+#[aoc_generator(day2)]
+fn generator(input: &str) -> solution::Result<<Day2 as Solution>::Input> {
+    Day2::parse(input)
+}
+
+#[aoc(day2, part1)]
+fn run_part1(input: &<Day2 as Solution>::Input) -> solution::Result<Answer> {
+    Day2::part1(input)
+}
+
+#[aoc(day2, part2)]
+fn run_part2(input: &<Day2 as Solution>::Input) -> solution::Result<Answer> {
+    Day2::part2(input)
+}
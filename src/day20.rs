@@ -1,231 +1,885 @@
+use crate::animate::{Animate, Frame};
+use crate::dot::{Digraph, ToDot};
+use crate::render::{Cell, Color};
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
 use lazy_static::lazy_static;
 use regex::Regex;
-use std::cell::RefCell;
-use std::collections::{HashMap, HashSet};
-use std::rc::Rc;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::iter;
+use std::ops::{Deref, DerefMut, Range};
 use std::str::FromStr;
+use std::sync::Arc;
+use tracing::{debug, instrument, trace};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum Pulse {
+pub enum Pulse {
     High,
     Low,
 }
 
+impl Pulse {
+    fn from_high(high: bool) -> Self {
+        match high {
+            true => Pulse::High,
+            false => Pulse::Low,
+        }
+    }
+}
+
+type ModuleId = usize;
+
+/// A fixed number of bits, packed into words.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-enum FlipFlopState {
-    On,
-    Off,
+pub struct Bitset(Vec<u64>);
+
+impl Bitset {
+    fn new(len: usize) -> Self {
+        Self(vec![0; len.div_ceil(64)])
+    }
+
+    fn get(&self, bit: usize) -> bool {
+        self.0[bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    fn set(&mut self, bit: usize, value: bool) {
+        if value {
+            self.0[bit / 64] |= 1 << (bit % 64);
+        } else {
+            self.0[bit / 64] &= !(1 << (bit % 64));
+        }
+    }
+
+    /// Flips a bit, returning its new value.
+    fn toggle(&mut self, bit: usize) -> bool {
+        self.0[bit / 64] ^= 1 << (bit % 64);
+        self.get(bit)
+    }
+
+    /// The words covering `bits`, each with a mask of the bits in range.
+    fn masks(bits: Range<usize>) -> impl Iterator<Item = (usize, u64)> {
+        let words = bits.start / 64..bits.end.div_ceil(64);
+
+        words.map(move |word| {
+            let lo = bits.start.max(word * 64) - word * 64;
+            let hi = bits.end.min(word * 64 + 64) - word * 64;
+
+            // an empty range would shift by the full width, so gets no bits
+            let ones = u64::MAX.checked_shr(64 - (hi - lo) as u32).unwrap_or(0);
+
+            (word, ones << lo)
+        })
+    }
+
+    fn count(&self, bits: Range<usize>) -> usize {
+        Self::masks(bits)
+            .map(|(word, mask)| (self.0[word] & mask).count_ones() as usize)
+            .sum()
+    }
+
+    fn all(&self, bits: Range<usize>) -> bool {
+        Self::masks(bits).all(|(word, mask)| self.0[word] & mask == mask)
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum Behavior {
-    FlipFlop { state: FlipFlopState },
-    Conjunction { memory: HashMap<String, Pulse> },
-    Broadcaster,
-    Sink,
+/// The bits of state that belong to one module, numbered from zero.
+pub struct ModuleState<'a, B> {
+    name: &'a str,
+    bits: B,
+    range: Range<usize>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct Module {
-    name: String,
-    behavior: Behavior,
-    inputs: Vec<String>,
-    outputs: Vec<String>,
+impl<B: Deref<Target = Bitset>> ModuleState<'_, B> {
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    pub fn get(&self, bit: usize) -> bool {
+        self.bits.get(self.range.start + bit)
+    }
+
+    /// How many of the module's bits are set.
+    pub fn count(&self) -> usize {
+        self.bits.count(self.range.clone())
+    }
+
+    pub fn all(&self) -> bool {
+        self.bits.all(self.range.clone())
+    }
 }
 
-impl Module {
-    fn flip_flop(name: String, inputs: Vec<String>, outputs: Vec<String>) -> Self {
-        Self {
-            name,
-            inputs,
-            outputs,
-            behavior: Behavior::FlipFlop {
-                state: FlipFlopState::Off,
-            },
-        }
+impl<B: DerefMut<Target = Bitset>> ModuleState<'_, B> {
+    pub fn set(&mut self, bit: usize, value: bool) {
+        self.bits.set(self.range.start + bit, value)
     }
 
-    fn conjunction(name: String, inputs: Vec<String>, outputs: Vec<String>) -> Self {
-        let memory = inputs.iter().map(|s| (s.clone(), Pulse::Low)).collect();
+    /// Flips a bit, returning its new value.
+    pub fn toggle(&mut self, bit: usize) -> bool {
+        self.bits.toggle(self.range.start + bit)
+    }
+}
 
-        Self {
-            name,
-            inputs,
-            outputs,
-            behavior: Behavior::Conjunction { memory },
+/// How a kind of module responds to pulses. Modules keep no state of their
+/// own; each gets the bits it asks for in the configuration's state.
+pub trait ModuleBehavior: fmt::Debug + Send + Sync {
+    /// How many bits of state a module with `inputs` distinct inputs needs.
+    fn state_bits(&self, _inputs: usize) -> usize {
+        0
+    }
+
+    /// Handles a pulse arriving on the module's `input`th input, returning
+    /// the pulse to send to every output, if any. The button counts as the
+    /// broadcaster's last input.
+    fn handle(
+        &self,
+        state: &mut ModuleState<&mut Bitset>,
+        input: usize,
+        pulse: Pulse,
+    ) -> Option<Pulse>;
+
+    /// A few characters summing up the module's state, for animations.
+    fn describe(&self, _state: &ModuleState<&Bitset>) -> String {
+        String::new()
+    }
+
+    /// Graphviz attributes for the module's node.
+    fn dot_attributes(&self) -> &'static [(&'static str, &'static str)];
+}
+
+/// `%`: toggles on each low pulse, sending high when it turns on and low
+/// when it turns off. High pulses are ignored.
+#[derive(Debug)]
+pub struct FlipFlop;
+
+impl ModuleBehavior for FlipFlop {
+    fn state_bits(&self, _inputs: usize) -> usize {
+        1
+    }
+
+    fn handle(
+        &self,
+        state: &mut ModuleState<&mut Bitset>,
+        _: usize,
+        pulse: Pulse,
+    ) -> Option<Pulse> {
+        match pulse {
+            Pulse::High => None,
+            Pulse::Low => Some(Pulse::from_high(state.toggle(0))),
         }
     }
 
-    fn broadcaster(name: String, inputs: Vec<String>, outputs: Vec<String>) -> Self {
-        Self {
-            name,
-            inputs,
-            outputs,
-            behavior: Behavior::Broadcaster,
+    fn describe(&self, state: &ModuleState<&Bitset>) -> String {
+        match state.get(0) {
+            true => "on".to_string(),
+            false => "off".to_string(),
         }
     }
 
-    fn sink(name: String, inputs: Vec<String>) -> Self {
+    fn dot_attributes(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("shape", "box"),
+            ("style", "filled"),
+            ("fillcolor", "lightblue"),
+        ]
+    }
+}
+
+/// `&`: remembers the last pulse from each input, and sends low once they
+/// were all high.
+#[derive(Debug)]
+pub struct Conjunction;
+
+impl ModuleBehavior for Conjunction {
+    fn state_bits(&self, inputs: usize) -> usize {
+        inputs
+    }
+
+    fn handle(
+        &self,
+        state: &mut ModuleState<&mut Bitset>,
+        input: usize,
+        pulse: Pulse,
+    ) -> Option<Pulse> {
+        state.set(input, pulse == Pulse::High);
+
+        Some(Pulse::from_high(!state.all()))
+    }
+
+    fn describe(&self, state: &ModuleState<&Bitset>) -> String {
+        format!("{}/{}", state.count(), state.len())
+    }
+
+    fn dot_attributes(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("shape", "invhouse"),
+            ("style", "filled"),
+            ("fillcolor", "khaki"),
+        ]
+    }
+}
+
+/// No prefix: passes every pulse on.
+#[derive(Debug)]
+pub struct Broadcaster;
+
+impl ModuleBehavior for Broadcaster {
+    fn handle(&self, _: &mut ModuleState<&mut Bitset>, _: usize, pulse: Pulse) -> Option<Pulse> {
+        Some(pulse)
+    }
+
+    fn dot_attributes(&self) -> &'static [(&'static str, &'static str)] {
+        &[("shape", "doubleoctagon"), ("style", "bold")]
+    }
+}
+
+/// A module that is only ever sent to, such as `rx`.
+#[derive(Debug)]
+pub struct Sink;
+
+impl ModuleBehavior for Sink {
+    fn handle(&self, _: &mut ModuleState<&mut Bitset>, _: usize, _: Pulse) -> Option<Pulse> {
+        None
+    }
+
+    fn dot_attributes(&self) -> &'static [(&'static str, &'static str)] {
+        &[
+            ("shape", "doublecircle"),
+            ("style", "filled"),
+            ("fillcolor", "salmon"),
+        ]
+    }
+}
+
+/// The behaviour for each prefix a module's name can have.
+#[derive(Debug)]
+pub struct Registry {
+    behaviors: HashMap<Option<char>, Arc<dyn ModuleBehavior>>,
+}
+
+impl Registry {
+    /// A registry that knows no prefixes, not even the built-in ones.
+    pub fn empty() -> Self {
         Self {
-            name,
-            inputs,
-            outputs: vec![],
-            behavior: Behavior::Sink,
+            behaviors: HashMap::new(),
         }
     }
 
-    fn handle_pulse(&mut self, input: &str, pulse: &Pulse) -> Option<Pulse> {
-        match (&mut self.behavior, pulse) {
-            (Behavior::FlipFlop { .. }, Pulse::High) => None,
-            (Behavior::FlipFlop { state }, Pulse::Low) => match state {
-                FlipFlopState::Off => {
-                    *state = FlipFlopState::On;
-                    Some(Pulse::High)
-                }
-                FlipFlopState::On => {
-                    *state = FlipFlopState::Off;
-                    Some(Pulse::Low)
-                }
-            },
-            (Behavior::Conjunction { ref mut memory }, pulse) => {
-                memory.insert(input.to_string(), *pulse);
-                if memory.values().all(|p| p == &Pulse::High) {
-                    Some(Pulse::Low)
-                } else {
-                    Some(Pulse::High)
-                }
-            }
-            (Behavior::Broadcaster, pulse) => Some(*pulse),
-            (Behavior::Sink, _) => None,
-        }
+    /// Gives modules written with `prefix` the behaviour `behavior`; `None`
+    /// is for names written without a prefix.
+    pub fn register(
+        &mut self,
+        prefix: Option<char>,
+        behavior: impl ModuleBehavior + 'static,
+    ) -> &mut Self {
+        self.behaviors.insert(prefix, Arc::new(behavior));
+        self
+    }
+
+    fn get(&self, prefix: Option<char>) -> Option<Arc<dyn ModuleBehavior>> {
+        self.behaviors.get(&prefix).cloned()
+    }
+}
+
+/// The puzzle's flip-flops, conjunctions and broadcaster, along with the
+/// prototyping kinds below.
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+
+        registry
+            .register(Some('%'), FlipFlop)
+            .register(Some('&'), Conjunction)
+            .register(None, Broadcaster)
+            .register(Some('!'), Inverter)
+            .register(Some('/'), Divider)
+            .register(Some('~'), DelayLine)
+            .register(Some('?'), Logger);
+
+        registry
     }
+}
+
+/// A module's wiring. Its state is kept apart, in `ModuleConfig::state`.
+#[derive(Debug, Clone)]
+struct Module {
+    name: String,
+    prefix: Option<char>,
+    behavior: Arc<dyn ModuleBehavior>,
+    inputs: Vec<ModuleId>,
+    /// Each destination, with which of its inputs this module is.
+    outputs: Vec<(ModuleId, usize)>,
+    state: Range<usize>,
+}
+
+/// Every module, interned by name.
+#[derive(Debug)]
+struct Modules {
+    modules: Vec<Module>,
+    ids: HashMap<String, ModuleId>,
+    /// The broadcaster, and which of its inputs the button is.
+    button: (ModuleId, usize),
+}
+
+/// A pulse on its way to a module. Pulses from the button have no sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Sent {
+    from: Option<ModuleId>,
+    to: ModuleId,
+    input: usize,
+    pulse: Pulse,
+}
+
+/// The wiring is shared between clones, so cloning only copies the state
+/// bits.
+#[derive(Debug, Clone)]
+pub struct ModuleConfig {
+    modules: Arc<Modules>,
+    state: Bitset,
+}
+
+/// Every flip-flop bit and conjunction memory in a circuit, as taken by
+/// `ModuleConfig::state`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CircuitState(Bitset);
+
+/// After `start` presses, a circuit is in a state it comes back to every
+/// `length` presses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    pub start: usize,
+    pub length: usize,
+}
+
+/// The shortest `p` for which `items[i] == items[i + p]` wherever both
+/// exist, by way of the longest proper prefix that is also a suffix.
+fn shortest_period<T: PartialEq>(items: &[T]) -> usize {
+    let mut border = vec![0; items.len()];
 
-    fn handle_pulses(&mut self, input: &str, pulse: &Pulse) -> Vec<(String, Pulse)> {
-        let output = self.handle_pulse(input, pulse);
+    for i in 1..items.len() {
+        let mut k = border[i - 1];
 
-        match output {
-            Some(output) => self.outputs.iter().map(|o| (o.clone(), output)).collect(),
-            None => vec![],
+        while k > 0 && items[i] != items[k] {
+            k = border[k - 1];
         }
+
+        if items[i] == items[k] {
+            k += 1;
+        }
+
+        border[i] = k;
     }
+
+    items.len() - border.last().copied().unwrap_or(0)
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct ModuleConfig {
-    modules: RefCell<HashMap<String, Rc<RefCell<Module>>>>,
+/// The modules after a round of pulses has been handled, and the pulses
+/// that round sent on.
+struct PulseRound {
+    config: ModuleConfig,
+    pulses: Vec<Sent>,
 }
 
 impl ModuleConfig {
-    fn push_button(&mut self) -> (usize, usize) {
-        let mut low_pulse_count = 0usize;
-        let mut high_pulse_count = 0usize;
-
-        let mut pulses = vec![("button".to_string(), "broadcaster".to_string(), Pulse::Low)];
-        let modules = self.modules.borrow();
-
-        while !pulses.is_empty() {
-            low_pulse_count += pulses.iter().filter(|(_, _, p)| p == &Pulse::Low).count();
-            high_pulse_count += pulses.iter().filter(|(_, _, p)| p == &Pulse::High).count();
-
-            let mut next_pulses = vec![];
-
-            for (input, dest, pulse) in pulses {
-                if let Some(module) = modules.get(dest.as_str()) {
-                    let mut module = module.borrow_mut();
-                    let dest_pulses = module.handle_pulses(input.as_str(), &pulse);
-                    next_pulses.extend(
-                        dest_pulses
-                            .iter()
-                            .map(|(d, p)| (module.name.clone(), d.clone(), *p)),
-                    );
-                }
+    fn button_pulse(&self) -> Sent {
+        let (to, input) = self.modules.button;
+
+        Sent {
+            from: None,
+            to,
+            input,
+            pulse: Pulse::Low,
+        }
+    }
+
+    fn name(&self, id: Option<ModuleId>) -> &str {
+        id.map_or("button", |id| self.modules.modules[id].name.as_str())
+    }
+
+    /// Hands a pulse to its module, and queues whatever that sends on.
+    fn deliver(&mut self, sent: Sent, queue: &mut VecDeque<Sent>) {
+        let module = &self.modules.modules[sent.to];
+        let mut state = ModuleState {
+            name: &module.name,
+            bits: &mut self.state,
+            range: module.state.clone(),
+        };
+
+        if let Some(pulse) = module.behavior.handle(&mut state, sent.input, sent.pulse) {
+            queue.extend(module.outputs.iter().map(|&(to, input)| Sent {
+                from: Some(sent.to),
+                to,
+                input,
+                pulse,
+            }));
+        }
+    }
+
+    /// Presses the button and handles pulses in the order they were sent
+    /// until none are left, showing each one to `visit`.
+    fn press(&mut self, queue: &mut VecDeque<Sent>, mut visit: impl FnMut(&Sent)) {
+        queue.push_back(self.button_pulse());
+
+        while let Some(sent) = queue.pop_front() {
+            visit(&sent);
+            self.deliver(sent, queue);
+        }
+    }
+
+    /// Each round of pulses set off by one button press, starting with the
+    /// button's own pulse to the broadcaster.
+    fn button_press_rounds(&self) -> impl Iterator<Item = PulseRound> {
+        let mut config = self.clone();
+        let mut pulses = vec![config.button_pulse()];
+
+        let first = PulseRound {
+            config: config.clone(),
+            pulses: pulses.clone(),
+        };
+
+        iter::once(first).chain(iter::from_fn(move || {
+            let mut next_pulses = VecDeque::new();
+
+            for sent in pulses.drain(..) {
+                config.deliver(sent, &mut next_pulses);
+            }
+
+            pulses.extend(next_pulses);
+
+            if pulses.is_empty() {
+                None
+            } else {
+                Some(PulseRound {
+                    config: config.clone(),
+                    pulses: pulses.clone(),
+                })
             }
+        }))
+    }
+
+    /// A snapshot of every module's state.
+    pub fn state(&self) -> CircuitState {
+        CircuitState(self.state.clone())
+    }
+
+    /// Puts every module back as it was when `state` was taken. The state
+    /// must come from this configuration or a clone of it.
+    pub fn restore(&mut self, state: &CircuitState) {
+        self.state.clone_from(&state.0);
+    }
+
+    /// Presses the button once, returning how many low and high pulses
+    /// were sent.
+    pub fn press_button(&mut self) -> (usize, usize) {
+        let mut counts = (0, 0);
+
+        self.press(&mut VecDeque::new(), |sent| match sent.pulse {
+            Pulse::Low => counts.0 += 1,
+            Pulse::High => counts.1 += 1,
+        });
+
+        counts
+    }
+
+    /// Presses the button up to `limit` times from the current state, and
+    /// returns the running totals of low and high pulses after each press,
+    /// starting from zero, with the period if the state repeated.
+    fn presses_until_repeat(&self, limit: usize) -> (Vec<(usize, usize)>, Option<Period>) {
+        let mut config = self.clone();
+        let mut seen = HashMap::from([(config.state(), 0)]);
+        let mut totals = vec![(0, 0)];
+
+        for presses in 1..=limit {
+            let (low, high) = config.press_button();
+            let (total_low, total_high) = totals[presses - 1];
+            totals.push((total_low + low, total_high + high));
 
-            pulses = next_pulses;
+            if let Some(start) = seen.insert(config.state(), presses) {
+                let period = Period {
+                    start,
+                    length: presses - start,
+                };
+
+                return (totals, Some(period));
+            }
         }
 
-        (low_pulse_count, high_pulse_count)
+        (totals, None)
     }
 
-    fn pulse_score(&self, n: usize) -> usize {
-        let mut module_config = self.clone();
-        let mut low_pulse_count = 0usize;
-        let mut high_pulse_count = 0usize;
+    /// When the whole circuit first falls into a loop within `limit`
+    /// presses. A start of zero means it returns to the state it is in now.
+    pub fn find_period(&self, limit: usize) -> Option<Period> {
+        self.presses_until_repeat(limit).1
+    }
+
+    /// The shortest period of each stateful module's own state, if it
+    /// repeats at least twice over the second half of `presses` presses.
+    /// Skipping the first half lets modules settle into their loops first.
+    /// Modules are in name order.
+    pub fn module_periods(&self, presses: usize) -> Vec<(&str, Option<usize>)> {
+        let mut config = self.clone();
+        let modules = &self.modules.modules;
 
-        for _ in 0..n {
-            let (low, high) = module_config.push_button();
+        let module_states = |config: &ModuleConfig| {
+            modules
+                .iter()
+                .map(|module| {
+                    module
+                        .state
+                        .clone()
+                        .map(|bit| config.state.get(bit))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
 
-            low_pulse_count += low;
-            high_pulse_count += high;
+        let mut history = vec![module_states(&config)];
+        for _ in 0..presses {
+            config.press_button();
+            history.push(module_states(&config));
         }
 
-        low_pulse_count * high_pulse_count
+        let mut periods = modules
+            .iter()
+            .enumerate()
+            .filter(|(_, module)| !module.state.is_empty())
+            .map(|(id, module)| {
+                let states = history[presses / 2..]
+                    .iter()
+                    .map(|h| &h[id])
+                    .collect::<Vec<_>>();
+                let period = shortest_period(&states);
+
+                (
+                    module.name.as_str(),
+                    (2 * period <= states.len()).then_some(period),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        periods.sort();
+        periods
     }
 
-    fn button_presses_until(&self, dest_module: String, dest_pulse: Pulse) -> usize {
-        let module_config = self.clone();
+    /// The product of low and high pulses sent over `n` presses, or `None`
+    /// if a count or the product overflows. Once the circuit loops, the
+    /// remaining presses are counted a whole period at a time.
+    #[instrument(level = "debug", skip(self))]
+    pub fn pulse_score(&self, n: usize) -> Option<usize> {
+        let (totals, period) = self.presses_until_repeat(n);
+
+        let (low, high) = match period {
+            Some(Period { start, length }) if n > start + length => {
+                debug!(start, length, "circuit loops");
+
+                let (cycles, rest) = ((n - start) / length, (n - start) % length);
+                let per_cycle = (
+                    totals[start + length].0 - totals[start].0,
+                    totals[start + length].1 - totals[start].1,
+                );
+
+                let extrapolate = |so_far: usize, per_cycle: usize| {
+                    cycles.checked_mul(per_cycle)?.checked_add(so_far)
+                };
+
+                (
+                    extrapolate(totals[start + rest].0, per_cycle.0)?,
+                    extrapolate(totals[start + rest].1, per_cycle.1)?,
+                )
+            }
+            _ => totals[n],
+        };
+
+        low.checked_mul(high)
+    }
+
+    #[instrument(level = "debug", skip(self))]
+    fn button_presses_until(&self, dest_module: &str, dest_pulse: Pulse) -> Option<usize> {
+        let mut module_config = self.clone();
+        let mut queue = VecDeque::new();
         let mut num_presses = 0usize;
         let mut cond_reached = false;
 
-        let modules = module_config.modules.borrow();
+        let dest = Some(*self.modules.ids.get(dest_module)?);
 
         while !cond_reached {
             num_presses += 1;
-            println!("num_presses: {}", num_presses);
-            let mut pulses = vec![("button".to_string(), "broadcaster".to_string(), Pulse::Low)];
-
-            while !cond_reached && !pulses.is_empty() {
-                println!("pulse");
-                let mut next_pulses = vec![];
-
-                for (input, dest, pulse) in pulses {
-                    if let Some(module) = modules.get(dest.as_str()) {
-                        let mut module = module.borrow_mut();
-                        let dest_pulses = module.handle_pulses(input.as_str(), &pulse);
-
-                        if dest_pulses
-                            .iter()
-                            .any(|(d, p)| p == &dest_pulse && d == &dest_module)
-                        {
-                            cond_reached = true;
-                            break;
-                        }
-
-                        next_pulses.extend(
-                            dest_pulses
-                                .iter()
-                                .map(|(d, p)| (module.name.clone(), d.clone(), *p)),
-                        );
-                    }
-                }
+            trace!(num_presses);
+
+            module_config.press(&mut queue, |sent| {
+                cond_reached |=
+                    sent.from.is_some() && Some(sent.to) == dest && sent.pulse == dest_pulse;
+            });
+        }
+
+        Some(num_presses)
+    }
+}
+
+impl PulseRound {
+    /// One line per module, highlighted when a pulse is on its way to it.
+    fn cells(&self) -> Frame {
+        let config = &self.config;
+        let mut modules = config
+            .modules
+            .modules
+            .iter()
+            .enumerate()
+            .collect::<Vec<_>>();
+        modules.sort_by_key(|(_, module)| &module.name);
+
+        modules
+            .into_iter()
+            .map(|(id, module)| {
+                let state = module.behavior.describe(&ModuleState {
+                    name: &module.name,
+                    bits: &config.state,
+                    range: module.state.clone(),
+                });
 
-                pulses = next_pulses;
+                let incoming = self
+                    .pulses
+                    .iter()
+                    .filter(|sent| sent.to == id)
+                    .map(|sent| match sent.pulse {
+                        Pulse::High => format!("high from {}", config.name(sent.from)),
+                        Pulse::Low => format!("low from {}", config.name(sent.from)),
+                    })
+                    .collect::<Vec<_>>();
+
+                let outputs = module
+                    .outputs
+                    .iter()
+                    .map(|&(to, _)| config.name(Some(to)))
+                    .collect::<Vec<_>>();
+
+                let line = format!(
+                    "{}{:<12} {:<5} -> {:<30} {}",
+                    module.prefix.unwrap_or(' '),
+                    module.name,
+                    state,
+                    outputs.join(", "),
+                    incoming.join(", ")
+                );
+
+                let color = if self
+                    .pulses
+                    .iter()
+                    .any(|sent| sent.to == id && sent.pulse == Pulse::High)
+                {
+                    Some(Color::Red)
+                } else if !incoming.is_empty() {
+                    Some(Color::Blue)
+                } else {
+                    None
+                };
+
+                line.trim_end()
+                    .chars()
+                    .map(|c| match color {
+                        Some(color) => Cell::highlighted(c, color, c),
+                        None => Cell::new(c),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// One frame per round of pulses during a single button press.
+impl Animate for ModuleConfig {
+    fn frames(&self) -> Box<dyn Iterator<Item = Frame> + '_> {
+        Box::new(self.button_press_rounds().map(|round| round.cells()))
+    }
+}
+
+/// Modules are shaped by behaviour: flip-flops are boxes, conjunctions
+/// inverted houses, the broadcaster an octagon and untyped sinks such as
+/// `rx` double circles. Each behaviour picks its own attributes.
+impl ToDot for ModuleConfig {
+    fn to_dot(&self) -> String {
+        let mut graph = Digraph::new("modules");
+
+        let mut modules = self.modules.modules.iter().collect::<Vec<_>>();
+        modules.sort_by_key(|module| &module.name);
+
+        for module in &modules {
+            let name = &module.name;
+            let label = match module.prefix {
+                Some(prefix) => format!("{}{}", prefix, name),
+                None => name.to_string(),
+            };
+            let attrs = module.behavior.dot_attributes();
+
+            graph.node(name, &[attrs, &[("label", label.as_str())]].concat());
+        }
+
+        for module in modules {
+            for &(to, _) in &module.outputs {
+                graph.edge(&module.name, self.name(Some(to)), &[]);
             }
         }
 
-        num_presses
+        graph.finish()
     }
 }
 
-lazy_static! {
-    static ref RE_MODULE: Regex = Regex::new(r"([%|&]?)(\w+|broadcaster) -> (.*)").unwrap();
+/// `!`: sends the opposite of every pulse.
+#[derive(Debug)]
+pub struct Inverter;
+
+impl ModuleBehavior for Inverter {
+    fn handle(&self, _: &mut ModuleState<&mut Bitset>, _: usize, pulse: Pulse) -> Option<Pulse> {
+        match pulse {
+            Pulse::High => Some(Pulse::Low),
+            Pulse::Low => Some(Pulse::High),
+        }
+    }
+
+    fn dot_attributes(&self) -> &'static [(&'static str, &'static str)] {
+        &[("shape", "triangle"), ("orientation", "270")]
+    }
 }
 
-impl FromStr for ModuleConfig {
-    type Err = ();
+/// `/`: counts low pulses in two bits and sends a low pulse each time the
+/// count wraps, dividing them by four. High pulses are ignored.
+#[derive(Debug)]
+pub struct Divider;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let module_lines = s.lines().collect::<Vec<_>>();
+impl ModuleBehavior for Divider {
+    fn state_bits(&self, _inputs: usize) -> usize {
+        2
+    }
+
+    fn handle(
+        &self,
+        state: &mut ModuleState<&mut Bitset>,
+        _: usize,
+        pulse: Pulse,
+    ) -> Option<Pulse> {
+        if pulse == Pulse::High || state.toggle(0) || state.toggle(1) {
+            return None;
+        }
+
+        Some(Pulse::Low)
+    }
+
+    fn describe(&self, state: &ModuleState<&Bitset>) -> String {
+        let count = usize::from(state.get(0)) + 2 * usize::from(state.get(1));
+
+        format!("{}/4", count)
+    }
+
+    fn dot_attributes(&self) -> &'static [(&'static str, &'static str)] {
+        &[("shape", "octagon")]
+    }
+}
+
+/// `~`: sends the pulse it received before this one, starting from low.
+#[derive(Debug)]
+pub struct DelayLine;
+
+impl ModuleBehavior for DelayLine {
+    fn state_bits(&self, _inputs: usize) -> usize {
+        1
+    }
+
+    fn handle(
+        &self,
+        state: &mut ModuleState<&mut Bitset>,
+        _: usize,
+        pulse: Pulse,
+    ) -> Option<Pulse> {
+        let previous = Pulse::from_high(state.get(0));
+        state.set(0, pulse == Pulse::High);
 
-        let mut module_names = HashSet::<String>::new();
-        let mut inputs = HashMap::<String, Vec<String>>::new();
+        Some(previous)
+    }
+
+    fn describe(&self, state: &ModuleState<&Bitset>) -> String {
+        match state.get(0) {
+            true => "high".to_string(),
+            false => "low".to_string(),
+        }
+    }
+
+    fn dot_attributes(&self) -> &'static [(&'static str, &'static str)] {
+        &[("shape", "cds")]
+    }
+}
+
+/// `?`: logs every pulse at debug level and passes it on.
+#[derive(Debug)]
+pub struct Logger;
+
+impl ModuleBehavior for Logger {
+    fn handle(
+        &self,
+        state: &mut ModuleState<&mut Bitset>,
+        input: usize,
+        pulse: Pulse,
+    ) -> Option<Pulse> {
+        debug!(module = state.name(), input, ?pulse, "pulse received");
+
+        Some(pulse)
+    }
+
+    fn dot_attributes(&self) -> &'static [(&'static str, &'static str)] {
+        &[("shape", "note")]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    Invalid,
+    UnknownPrefix { prefix: Option<char> },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Invalid => write!(f, "invalid module configuration"),
+            ConfigError::UnknownPrefix {
+                prefix: Some(prefix),
+            } => {
+                write!(f, "no module behaviour for prefix {:?}", prefix)
+            }
+            ConfigError::UnknownPrefix { prefix: None } => {
+                write!(f, "no module behaviour for names without a prefix")
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+lazy_static! {
+    static ref RE_MODULE: Regex = Regex::new(r"([^\w\s]?)(\w+) -> (.*)").unwrap();
+}
+
+impl ModuleConfig {
+    /// Parses a configuration whose module prefixes are looked up in
+    /// `registry`. Fails on any prefix the registry doesn't know.
+    pub fn parse_with(s: &str, registry: &Registry) -> Result<Self, ConfigError> {
+        // the button is always wired to the broadcaster, even if nothing else is
+        let mut module_names = BTreeSet::from(["broadcaster".to_string()]);
         let mut outputs = HashMap::<String, Vec<String>>::new();
-        let mut behaviors = HashMap::<String, String>::new();
+        let mut behaviors = HashMap::<String, Arc<dyn ModuleBehavior>>::new();
+        let mut prefixes = HashMap::<String, Option<char>>::new();
 
-        for line in module_lines {
-            let caps = RE_MODULE.captures(line).ok_or(())?;
-            let behavior = caps.get(1).unwrap().as_str().to_string();
+        for line in s.lines() {
+            let caps = RE_MODULE.captures(line).ok_or(ConfigError::Invalid)?;
+            let prefix = caps.get(1).unwrap().as_str().chars().next();
             let name = caps.get(2).unwrap().as_str().to_string();
             let module_outputs = caps
                 .get(3)
@@ -235,52 +889,153 @@ impl FromStr for ModuleConfig {
                 .map(|s| s.to_string())
                 .collect::<Vec<_>>();
 
-            for output in &module_outputs {
-                inputs.entry(output.clone()).or_default().push(name.clone());
-                module_names.insert(output.clone());
-            }
+            module_names.extend(module_outputs.iter().cloned());
+            module_names.insert(name.clone());
 
             outputs.insert(name.clone(), module_outputs);
+            let behavior = registry
+                .get(prefix)
+                .ok_or(ConfigError::UnknownPrefix { prefix })?;
+
             behaviors.insert(name.clone(), behavior);
+            prefixes.insert(name, prefix);
+        }
 
-            module_names.insert(name.clone());
+        let ids = module_names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (name.clone(), id))
+            .collect::<HashMap<_, _>>();
+
+        let outputs = module_names
+            .iter()
+            .map(|name| {
+                outputs
+                    .remove(name)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|output| ids[output])
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut inputs = vec![vec![]; module_names.len()];
+        for (id, module_outputs) in outputs.iter().enumerate() {
+            for &output in module_outputs {
+                if !inputs[output].contains(&id) {
+                    inputs[output].push(id);
+                }
+            }
         }
 
-        let modules = module_names
+        let broadcaster = ids["broadcaster"];
+        let sink: Arc<dyn ModuleBehavior> = Arc::new(Sink);
+        let mut state_len = 0;
+
+        let mut modules = module_names
             .into_iter()
-            .map(|name| {
-                let behavior = behaviors.get(&name).map(|s| s.as_str());
-                let mod_inputs = inputs.remove(&name).unwrap_or_default();
-                let mod_outputs = outputs.remove(&name).unwrap_or_default();
+            .zip(inputs)
+            .enumerate()
+            .map(|(id, (name, inputs))| {
+                let behavior = behaviors.remove(&name).unwrap_or_else(|| sink.clone());
 
-                (
-                    name.clone(),
-                    Rc::new(RefCell::new(match behavior {
-                        Some("%") => Module::flip_flop(name, mod_inputs, mod_outputs),
-                        Some("&") => Module::conjunction(name, mod_inputs, mod_outputs),
-                        Some(_) => Module::broadcaster(name, mod_inputs, mod_outputs),
-                        None => Module::sink(name, mod_inputs),
-                    })),
-                )
+                let bits = behavior.state_bits(inputs.len() + usize::from(id == broadcaster));
+                let state = state_len..state_len + bits;
+                state_len += bits;
+
+                Module {
+                    prefix: prefixes.remove(&name).flatten(),
+                    name,
+                    behavior,
+                    inputs,
+                    outputs: vec![],
+                    state,
+                }
             })
-            .collect::<HashMap<_, _>>()
-            .into();
+            .collect::<Vec<_>>();
+
+        // the button comes after the broadcaster's other inputs
+        let input = |modules: &[Module], from: ModuleId, to: ModuleId| {
+            let inputs = &modules[to].inputs;
+            inputs
+                .iter()
+                .position(|&i| i == from)
+                .unwrap_or(inputs.len())
+        };
+
+        for (id, module_outputs) in outputs.into_iter().enumerate() {
+            modules[id].outputs = module_outputs
+                .into_iter()
+                .map(|to| (to, input(&modules, id, to)))
+                .collect();
+        }
+
+        let button = (broadcaster, input(&modules, usize::MAX, broadcaster));
+
+        Ok(Self {
+            modules: Arc::new(Modules {
+                modules,
+                ids,
+                button,
+            }),
+            state: Bitset::new(state_len),
+        })
+    }
+}
+
+impl FromStr for ModuleConfig {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, &Registry::default())
+    }
+}
+
+fn part1(config: &ModuleConfig) -> solution::Result<usize> {
+    let score = config.pulse_score(1000).ok_or("too many pulses to score")?;
+
+    Ok(score)
+}
+
+fn part2(config: &ModuleConfig) -> solution::Result<usize> {
+    let presses = config
+        .button_presses_until("rx", Pulse::Low)
+        .ok_or("the circuit has no rx module")?;
+
+    Ok(presses)
+}
+
+pub struct Day20;
+
+impl Solution for Day20 {
+    const DAY: u32 = 20;
+
+    type Input = ModuleConfig;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        Ok(input.parse::<ModuleConfig>()?)
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input)?.into())
+    }
 
-        Ok(Self { modules })
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input)?.into())
     }
 }
 
 #[aoc_generator(day20)]
-fn parse_input(input: &str) -> ModuleConfig {
-    input.parse().unwrap()
+fn generator(input: &str) -> solution::Result<<Day20 as Solution>::Input> {
+    Day20::parse(input)
 }
 
 #[aoc(day20, part1)]
-fn part1(config: &ModuleConfig) -> usize {
-    config.pulse_score(1000)
+fn run_part1(input: &<Day20 as Solution>::Input) -> solution::Result<Answer> {
+    Day20::part1(input)
 }
 
 #[aoc(day20, part2)]
-fn part2(config: &ModuleConfig) -> usize {
-    config.button_presses_until("rx".to_string(), Pulse::Low)
+fn run_part2(input: &<Day20 as Solution>::Input) -> solution::Result<Answer> {
+    Day20::part2(input)
 }
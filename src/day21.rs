@@ -1,3 +1,5 @@
+use crate::render::{Cell, Color, Render};
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
 use pathfinding::prelude::dfs_reach;
 use std::collections::{HashMap, HashSet};
@@ -11,7 +13,7 @@ enum Plot {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct GardenMap {
+pub struct GardenMap {
     start: (isize, isize),
     width: isize,
     height: isize,
@@ -22,33 +24,37 @@ impl FromStr for GardenMap {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let width = s.lines().next().ok_or(())?.chars().count();
+        if s.lines().any(|line| line.chars().count() != width) {
+            return Err(());
+        }
+
         let data = s
             .lines()
             .enumerate()
             .flat_map(|(y, line)| {
                 line.chars().enumerate().map(move |(x, c)| {
-                    (
-                        (y as isize, x as isize),
-                        match c {
-                            'S' => Plot::Start,
-                            '.' => Plot::Garden,
-                            '#' => Plot::Rock,
-                            _ => panic!("invalid char"),
-                        },
-                    )
+                    let plot = match c {
+                        'S' => Plot::Start,
+                        '.' => Plot::Garden,
+                        '#' => Plot::Rock,
+                        _ => return Err(()),
+                    };
+
+                    Ok(((y as isize, x as isize), plot))
                 })
             })
-            .collect::<HashMap<_, _>>();
+            .collect::<Result<HashMap<_, _>, _>>()?;
 
         let start = data
             .iter()
             .find(|(_, plot)| **plot == Plot::Start)
             .map(|(pos, _)| *pos)
-            .unwrap();
+            .ok_or(())?;
 
-        let width = data.iter().map(|((_, x), _)| *x).max().unwrap() + 1;
+        let width = data.iter().map(|((_, x), _)| *x).max().ok_or(())? + 1;
 
-        let height = data.iter().map(|((y, _), _)| *y).max().unwrap() + 1;
+        let height = data.iter().map(|((y, _), _)| *y).max().ok_or(())? + 1;
 
         Ok(Self {
             start,
@@ -75,7 +81,7 @@ impl GardenMap {
         self.data.get(&(y, x))
     }
 
-    fn reachable_in_k_steps(&self, k: usize, with_repeats: bool) -> usize {
+    fn reachable_plots(&self, k: usize, with_repeats: bool) -> HashSet<(isize, isize)> {
         let start_node = (self.start, 0usize);
 
         let nodes = dfs_reach(start_node, |((y, x), steps)| {
@@ -107,23 +113,77 @@ impl GardenMap {
             neighbors
         })
         .filter(|(_, steps)| *steps == k)
+        .map(|(pos, _)| pos)
         .collect::<HashSet<_>>();
 
-        nodes.len()
+        nodes
+    }
+
+    fn reachable_in_k_steps(&self, k: usize, with_repeats: bool) -> usize {
+        self.reachable_plots(k, with_repeats).len()
     }
 }
 
-#[aoc_generator(day21)]
-fn parse_input(input: &str) -> GardenMap {
-    input.parse().unwrap()
+/// Plots the elf can reach in exactly 64 steps are marked `O`.
+impl Render for GardenMap {
+    fn cells(&self) -> Vec<Vec<Cell>> {
+        let reachable = self.reachable_plots(64, false);
+
+        let rows = (0..self.height).map(|y| {
+            let reachable = &reachable;
+
+            (0..self.width).map(move |x| match self.data.get(&(y, x)) {
+                _ if reachable.contains(&(y, x)) => Cell::highlighted('O', Color::Green, 'O'),
+                Some(Plot::Start) => Cell::new('S'),
+                Some(Plot::Garden) => Cell::new('.'),
+                Some(Plot::Rock) => Cell::new('#'),
+                None => Cell::new(' '),
+            })
+        });
+
+        rows.map(Iterator::collect).collect()
+    }
 }
 
-#[aoc(day21, part1)]
 fn part1(map: &GardenMap) -> usize {
     map.reachable_in_k_steps(64, false)
 }
 
-#[aoc(day21, part2)]
 fn part2(map: &GardenMap) -> usize {
     map.reachable_in_k_steps(26501365, true)
 }
+
+pub struct Day21;
+
+impl Solution for Day21 {
+    const DAY: u32 = 21;
+
+    type Input = GardenMap;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input.parse().map_err(|_| "invalid garden map".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
+#[aoc_generator(day21)]
+fn generator(input: &str) -> solution::Result<<Day21 as Solution>::Input> {
+    Day21::parse(input)
+}
+
+#[aoc(day21, part1)]
+fn run_part1(input: &<Day21 as Solution>::Input) -> solution::Result<Answer> {
+    Day21::part1(input)
+}
+
+#[aoc(day21, part2)]
+fn run_part2(input: &<Day21 as Solution>::Input) -> solution::Result<Answer> {
+    Day21::part2(input)
+}
@@ -1,3 +1,4 @@
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
 use nalgebra::{Point2, Point3};
 use std::collections::{HashMap, HashSet};
@@ -46,7 +47,7 @@ impl Brick {
 }
 
 #[derive(Debug, Clone)]
-struct BrickStack {
+pub struct BrickStack {
     bricks: Vec<Rc<Brick>>,
     space: HashMap<Point3<usize>, Rc<Brick>>,
     reverse_space: HashMap<Rc<Brick>, Vec<Point3<usize>>>,
@@ -110,6 +111,15 @@ impl BrickStack {
     }
 }
 
+fn parse_coords(s: &str) -> Result<[usize; 3], ()> {
+    let coords = s
+        .split(',')
+        .map(|n| n.parse().map_err(|_| ()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    coords.try_into().map_err(|_| ())
+}
+
 impl FromStr for BrickStack {
     type Err = ();
 
@@ -117,19 +127,16 @@ impl FromStr for BrickStack {
         let bricks = s
             .lines()
             .map(|line| {
-                let (start_coords, end_coords) = line.split_once('~').unwrap();
+                let (start_coords, end_coords) = line.split_once('~').ok_or(())?;
 
-                let mut parts = start_coords.split(',');
-                let start_x = parts.next().unwrap().parse().unwrap();
-                let start_y = parts.next().unwrap().parse().unwrap();
-                let start_z = parts.next().unwrap().parse().unwrap();
+                let [start_x, start_y, start_z] = parse_coords(start_coords)?;
+                let [end_x, end_y, end_z] = parse_coords(end_coords)?;
 
-                let mut parts = end_coords.split(',');
-                let end_x = parts.next().unwrap().parse().unwrap();
-                let end_y = parts.next().unwrap().parse().unwrap();
-                let end_z = parts.next().unwrap().parse().unwrap();
+                if end_x < start_x || end_y < start_y || end_z < start_z {
+                    return Err(());
+                }
 
-                Rc::new(Brick {
+                Ok(Rc::new(Brick {
                     start_x,
                     start_y,
                     start_z,
@@ -137,9 +144,9 @@ impl FromStr for BrickStack {
                     end_y,
                     end_z,
                     height: end_z - start_z + 1,
-                })
+                }))
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         Ok(Self {
             bricks,
@@ -151,15 +158,35 @@ impl FromStr for BrickStack {
     }
 }
 
-#[aoc_generator(day22)]
-fn parse_input(input: &str) -> BrickStack {
-    input.parse().unwrap()
-}
-
-#[aoc(day22, part1)]
 fn part1(stack: &BrickStack) -> usize {
     let mut stack = stack.clone();
     stack.build_space();
 
     stack.count_destructible_bricks()
 }
+
+pub struct Day22;
+
+impl Solution for Day22 {
+    const DAY: u32 = 22;
+
+    type Input = BrickStack;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input.parse().map_err(|_| "invalid brick snapshot".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+}
+
+#[aoc_generator(day22)]
+fn generator(input: &str) -> solution::Result<<Day22 as Solution>::Input> {
+    Day22::parse(input)
+}
+
+#[aoc(day22, part1)]
+fn run_part1(input: &<Day22 as Solution>::Input) -> solution::Result<Answer> {
+    Day22::part1(input)
+}
@@ -1,6 +1,10 @@
+use crate::dot::{Digraph, ToDot};
+use crate::render::{Cell, Color, Render};
+use crate::solution::{self, Answer, Solution};
 use crate::util::Direction;
 use aoc_runner_derive::{aoc, aoc_generator};
 use pathfinding::prelude::yen;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -11,7 +15,7 @@ enum Tile {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct HikingMap {
+pub struct HikingMap {
     tiles: Vec<Vec<Tile>>,
     height: usize,
     width: usize,
@@ -79,12 +83,6 @@ impl FromStr for HikingMap {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct Pos {
-    y: isize,
-    x: isize,
-}
-
 impl HikingMap {
     fn successors_with_crampons(&self, pos: &(usize, usize)) -> Vec<(usize, usize)> {
         let mut successors = vec![];
@@ -133,7 +131,7 @@ impl HikingMap {
         self.successors_with_crampons(pos)
     }
 
-    fn longest_hike(&self) -> usize {
+    fn longest_hike_path(&self) -> Vec<(usize, usize)> {
         let paths = yen(
             &self.start,
             |pos| {
@@ -146,11 +144,15 @@ impl HikingMap {
             1000,
         );
         let (max_path, _) = paths
-            .iter()
+            .into_iter()
             .max_by_key(|(path, _)| path.len())
             .expect("no paths found");
 
-        max_path.len() - 1
+        max_path
+    }
+
+    fn longest_hike(&self) -> usize {
+        self.longest_hike_path().len() - 1
     }
 
     fn longest_hike_with_crampons(&self) -> usize {
@@ -174,17 +176,218 @@ impl HikingMap {
     }
 }
 
-#[aoc_generator(day23)]
-fn parse_input(input: &str) -> HikingMap {
-    input.parse().unwrap()
+/// A walkable corridor between two junctions, and its length.
+type Corridor = ((usize, usize), (usize, usize), usize);
+
+/// The tiles walked along a corridor, ending on the junction it leads to.
+type Trail = Vec<(usize, usize)>;
+
+impl HikingMap {
+    fn is_junction(&self, pos: &(usize, usize)) -> bool {
+        *pos == self.start
+            || *pos == self.end
+            || (self.tiles[pos.0][pos.1] != Tile::Forest
+                && self.successors_with_crampons(pos).len() > 2)
+    }
+
+    /// The tiles walked from `junction` through `first` up to and including
+    /// the next junction, or `None` if the corridor dead-ends.
+    fn corridor(&self, junction: (usize, usize), first: (usize, usize)) -> Option<Trail> {
+        let (mut prev, mut pos) = (junction, first);
+        let mut tiles = vec![first];
+
+        // a corridor has only one way on, unless a slope points back
+        while !self.is_junction(&pos) {
+            let next = self.successors(&pos).into_iter().find(|n| *n != prev)?;
+
+            (prev, pos) = (pos, next);
+            tiles.push(pos);
+        }
+
+        Some(tiles)
+    }
+
+    /// Every junction's corridors that can be walked down the slopes, as
+    /// the tiles of each one.
+    fn corridors(&self) -> Vec<((usize, usize), Trail)> {
+        let mut junctions = (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (y, x)))
+            .filter(|pos| self.is_junction(pos))
+            .collect::<Vec<_>>();
+        junctions.sort();
+
+        junctions
+            .into_iter()
+            .flat_map(|junction| {
+                self.successors(&junction)
+                    .into_iter()
+                    .filter_map(move |first| self.corridor(junction, first))
+                    .map(move |tiles| (junction, tiles))
+            })
+            .collect()
+    }
+
+    /// The start, the end and every tile where paths meet, with the length
+    /// of each corridor between them that can be walked down the slopes.
+    fn junction_graph(&self) -> Vec<Corridor> {
+        self.corridors()
+            .into_iter()
+            .map(|(from, tiles)| (from, tiles[tiles.len() - 1], tiles.len()))
+            .collect()
+    }
+
+    /// The tiles of the longest hike down the slopes, searched for over the
+    /// junction graph rather than tile by tile, or `None` if the end can't
+    /// be reached.
+    fn longest_hike_route(&self) -> Option<Vec<(usize, usize)>> {
+        let mut corridors = HashMap::<_, Vec<_>>::new();
+        for (from, tiles) in self.corridors() {
+            corridors.entry(from).or_default().push(tiles);
+        }
+
+        let mut route = self.longest_route_from(self.start, &corridors, &mut HashSet::new())?;
+        route.insert(0, self.start);
+
+        Some(route)
+    }
+
+    fn longest_route_from(
+        &self,
+        junction: (usize, usize),
+        corridors: &HashMap<(usize, usize), Vec<Trail>>,
+        visited: &mut HashSet<(usize, usize)>,
+    ) -> Option<Trail> {
+        if junction == self.end {
+            return Some(vec![]);
+        }
+
+        visited.insert(junction);
+
+        let mut longest: Option<Trail> = None;
+        for tiles in corridors.get(&junction).into_iter().flatten() {
+            let next = tiles[tiles.len() - 1];
+            if visited.contains(&next) {
+                continue;
+            }
+
+            if let Some(rest) = self.longest_route_from(next, corridors, visited) {
+                if longest
+                    .as_ref()
+                    .is_none_or(|l| l.len() < tiles.len() + rest.len())
+                {
+                    longest = Some(tiles.iter().copied().chain(rest).collect());
+                }
+            }
+        }
+
+        visited.remove(&junction);
+
+        longest
+    }
+}
+
+/// The junction graph, with corridors as edges labelled by their length.
+/// The start is green and the end red.
+impl ToDot for HikingMap {
+    fn to_dot(&self) -> String {
+        let id = |(y, x): (usize, usize)| format!("{},{}", y, x);
+        let mut graph = Digraph::new("hiking map");
+        graph.node_defaults(&[("shape", "circle")]);
+
+        graph.node(
+            &id(self.start),
+            &[("style", "filled"), ("fillcolor", "palegreen")],
+        );
+        graph.node(
+            &id(self.end),
+            &[("style", "filled"), ("fillcolor", "salmon")],
+        );
+
+        for (from, to, len) in self.junction_graph() {
+            graph.edge(&id(from), &id(to), &[("label", &len.to_string())]);
+        }
+
+        graph.finish()
+    }
+}
+
+impl Tile {
+    fn symbol(&self) -> char {
+        match self {
+            Tile::Path => '.',
+            Tile::Forest => '#',
+            Tile::Slope(Direction::Up) => '^',
+            Tile::Slope(Direction::Down) => 'v',
+            Tile::Slope(Direction::Left) => '<',
+            Tile::Slope(Direction::Right) => '>',
+        }
+    }
+}
+
+/// Every step of the longest hike down the slopes is marked `O`.
+impl Render for HikingMap {
+    fn cells(&self) -> Vec<Vec<Cell>> {
+        let hike = self
+            .longest_hike_route()
+            .unwrap_or_default()
+            .into_iter()
+            .collect::<HashSet<_>>();
+
+        let rows = self.tiles.iter().enumerate().map(|(y, row)| {
+            let hike = &hike;
+
+            row.iter().enumerate().map(move |(x, tile)| {
+                if hike.contains(&(y, x)) {
+                    Cell::highlighted(tile.symbol(), Color::Magenta, 'O')
+                } else {
+                    Cell::new(tile.symbol())
+                }
+            })
+        });
+
+        rows.map(Iterator::collect).collect()
+    }
 }
 
-#[aoc(day23, part1)]
 fn part1(hiking_map: &HikingMap) -> usize {
     hiking_map.longest_hike()
 }
 
-#[aoc(day23, part2)]
 fn part2(hiking_map: &HikingMap) -> usize {
     hiking_map.longest_hike_with_crampons()
 }
+
+pub struct Day23;
+
+impl Solution for Day23 {
+    const DAY: u32 = 23;
+
+    type Input = HikingMap;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input.parse().map_err(|_| "invalid hiking map".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
+#[aoc_generator(day23)]
+fn generator(input: &str) -> solution::Result<<Day23 as Solution>::Input> {
+    Day23::parse(input)
+}
+
+#[aoc(day23, part1)]
+fn run_part1(input: &<Day23 as Solution>::Input) -> solution::Result<Answer> {
+    Day23::part1(input)
+}
+
+#[aoc(day23, part2)]
+fn run_part2(input: &<Day23 as Solution>::Input) -> solution::Result<Answer> {
+    Day23::part2(input)
+}
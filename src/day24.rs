@@ -1,3 +1,4 @@
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
 use nalgebra::{Point2, Vector2, Vector3};
 use std::str::FromStr;
@@ -51,10 +52,23 @@ impl Hailstone {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-struct Hailstorm {
+pub struct Hailstorm {
     hailstones: Vec<Hailstone>,
 }
 
+fn parse_vector(s: &str) -> Result<Vector3<f64>, ()> {
+    let components = s
+        .trim()
+        .split(", ")
+        .map(|n| n.trim().parse().map_err(|_| ()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match components[..] {
+        [x, y, z] => Ok(Vector3::new(x, y, z)),
+        _ => Err(()),
+    }
+}
+
 impl FromStr for Hailstorm {
     type Err = ();
 
@@ -62,20 +76,13 @@ impl FromStr for Hailstorm {
         let hailstones = s
             .lines()
             .map(|line| {
-                let (pos, vel) = line.split_once("@").unwrap();
-                let mut parts = pos.trim().split(", ");
-                let x = parts.next().unwrap().trim().parse().unwrap();
-                let y = parts.next().unwrap().trim().parse().unwrap();
-                let z = parts.next().unwrap().trim().parse().unwrap();
-                let position = Vector3::new(x, y, z);
-                let mut parts = vel.trim().split(", ");
-                let x = parts.next().unwrap().trim().parse().unwrap();
-                let y = parts.next().unwrap().trim().parse().unwrap();
-                let z = parts.next().unwrap().trim().parse().unwrap();
-                let velocity = Vector3::new(x, y, z);
-                Hailstone { position, velocity }
+                let (pos, vel) = line.split_once("@").ok_or(())?;
+                let position = parse_vector(pos)?;
+                let velocity = parse_vector(vel)?;
+
+                Ok(Hailstone { position, velocity })
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         Ok(Self { hailstones })
     }
@@ -97,12 +104,6 @@ impl Hailstorm {
     }
 }
 
-#[aoc_generator(day24)]
-fn parse_input(input: &str) -> Hailstorm {
-    input.parse().unwrap()
-}
-
-#[aoc(day24, part1)]
 fn part1(storm: &Hailstorm) -> usize {
     storm.count_intersections(&TestArea {
         x_min: 200000000000000.0,
@@ -111,3 +112,29 @@ fn part1(storm: &Hailstorm) -> usize {
         y_max: 400000000000000.0,
     })
 }
+
+pub struct Day24;
+
+impl Solution for Day24 {
+    const DAY: u32 = 24;
+
+    type Input = Hailstorm;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input.parse().map_err(|_| "invalid hailstones".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+}
+
+#[aoc_generator(day24)]
+fn generator(input: &str) -> solution::Result<<Day24 as Solution>::Input> {
+    Day24::parse(input)
+}
+
+#[aoc(day24, part1)]
+fn run_part1(input: &<Day24 as Solution>::Input) -> solution::Result<Answer> {
+    Day24::part1(input)
+}
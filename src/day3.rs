@@ -1,3 +1,4 @@
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -27,7 +28,7 @@ struct Loc {
 }
 
 #[derive(Debug)]
-struct Engine {
+pub struct Engine {
     parts: Vec<Part>,
     engine_locations: Vec<Loc>,
 }
@@ -88,7 +89,10 @@ impl FromStr for Engine {
 
         for (y, line) in s.lines().enumerate() {
             for part_match in RE_PART.find_iter(line) {
-                let number = part_match.as_str().parse::<usize>().unwrap();
+                let number = part_match
+                    .as_str()
+                    .parse::<usize>()
+                    .map_err(|_| "part number too large")?;
 
                 parts.push(Part {
                     y: y as isize,
@@ -116,17 +120,45 @@ impl FromStr for Engine {
     }
 }
 
+fn part1(engine: &Engine) -> usize {
+    engine.sum_parts()
+}
+
+fn part2(engine: &Engine) -> usize {
+    engine.sum_gear_ratios()
+}
+
+pub struct Day3;
+
+impl Solution for Day3 {
+    const DAY: u32 = 3;
+
+    type Input = Engine;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input.parse().map_err(|_| "invalid engine schematic".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
 #[aoc_generator(day3)]
-fn parse_input(input: &str) -> Engine {
-    input.parse::<Engine>().unwrap()
+fn generator(input: &str) -> solution::Result<<Day3 as Solution>::Input> {
+    Day3::parse(input)
 }
 
 #[aoc(day3, part1)]
-fn part1(engine: &Engine) -> usize {
-    engine.sum_parts()
+fn run_part1(input: &<Day3 as Solution>::Input) -> solution::Result<Answer> {
+    Day3::part1(input)
 }
 
 #[aoc(day3, part2)]
-fn part2(engine: &Engine) -> usize {
-    engine.sum_gear_ratios()
+fn run_part2(input: &<Day3 as Solution>::Input) -> solution::Result<Answer> {
+    Day3::part2(input)
 }
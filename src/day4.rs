@@ -1,3 +1,4 @@
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
 use lazy_static::lazy_static;
 use regex::Regex;
@@ -21,11 +22,10 @@ impl Card {
     }
 
     fn count_winning(&self) -> usize {
-        return self
-            .winning_numbers
+        self.winning_numbers
             .iter()
             .filter(|n| self.card_numbers.contains(n))
-            .count();
+            .count()
     }
 }
 
@@ -58,7 +58,7 @@ impl FromStr for Card {
     }
 }
 
-struct Game {
+pub struct Game {
     cards: Vec<Card>,
 }
 
@@ -80,7 +80,9 @@ impl Game {
 
             for j in 0..count_winning {
                 let index = i + j + 1;
-                instance_counts.get_mut(&index).map(|c| *c += card_count);
+                if let Some(c) = instance_counts.get_mut(&index) {
+                    *c += card_count;
+                }
             }
         }
 
@@ -98,17 +100,45 @@ impl FromStr for Game {
     }
 }
 
+fn part1(game: &Game) -> usize {
+    game.score()
+}
+
+fn part2(game: &Game) -> usize {
+    game.cascading_card_count()
+}
+
+pub struct Day4;
+
+impl Solution for Day4 {
+    const DAY: u32 = 4;
+
+    type Input = Game;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input.parse().map_err(|_| "invalid scratchcards".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
 #[aoc_generator(day4)]
-fn parse_input(input: &str) -> Game {
-    input.parse().unwrap()
+fn generator(input: &str) -> solution::Result<<Day4 as Solution>::Input> {
+    Day4::parse(input)
 }
 
 #[aoc(day4, part1)]
-fn part1(game: &Game) -> usize {
-    game.score()
+fn run_part1(input: &<Day4 as Solution>::Input) -> solution::Result<Answer> {
+    Day4::part1(input)
 }
 
 #[aoc(day4, part2)]
-fn part2(game: &Game) -> usize {
-    game.cascading_card_count()
+fn run_part2(input: &<Day4 as Solution>::Input) -> solution::Result<Answer> {
+    Day4::part2(input)
 }
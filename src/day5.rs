@@ -1,6 +1,9 @@
+use crate::solution::{self, Answer, Solution};
 use crate::util::parse_numbers;
 use aoc_runner_derive::{aoc, aoc_generator};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
 use std::str::FromStr;
 
 #[derive(Debug, Clone)]
@@ -10,30 +13,6 @@ struct SeedRange {
     range_length: usize,
 }
 
-impl SeedRange {
-    fn map(&self, src: usize) -> Option<usize> {
-        if src < self.src_range_start || src >= self.src_range_start + self.range_length {
-            return None;
-        }
-
-        let offset = src - self.src_range_start;
-        let dest = self.dest_range_start + offset;
-
-        Some(dest)
-    }
-
-    fn map_rev(&self, dest: usize) -> Option<usize> {
-        if dest < self.dest_range_start || dest >= self.dest_range_start + self.range_length {
-            return None;
-        }
-
-        let offset = dest - self.dest_range_start;
-        let src = self.src_range_start + offset;
-
-        Some(src)
-    }
-}
-
 impl FromStr for SeedRange {
     type Err = ();
 
@@ -43,6 +22,10 @@ impl FromStr for SeedRange {
             return Err(());
         }
 
+        // both ends of the range have to be representable
+        numbers[0].checked_add(numbers[2]).ok_or(())?;
+        numbers[1].checked_add(numbers[2]).ok_or(())?;
+
         Ok(SeedRange {
             dest_range_start: numbers[0],
             src_range_start: numbers[1],
@@ -58,35 +41,13 @@ struct SeedMap {
     ranges: Vec<SeedRange>,
 }
 
-impl SeedMap {
-    fn map(&self, src: usize) -> usize {
-        for range in &self.ranges {
-            if let Some(dest) = range.map(src) {
-                return dest;
-            }
-        }
-
-        src
-    }
-
-    fn map_rev(&self, dest: usize) -> usize {
-        for range in &self.ranges {
-            if let Some(src) = range.map_rev(dest) {
-                return src;
-            }
-        }
-
-        dest
-    }
-}
-
 impl FromStr for SeedMap {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let lines = s.lines().collect::<Vec<_>>();
 
-        let mapping_str = lines[0].strip_suffix(" map:").ok_or(())?;
+        let mapping_str = lines.first().ok_or(())?.strip_suffix(" map:").ok_or(())?;
         let (source_category, dest_category) = mapping_str.split_once("-to-").ok_or(())?;
 
         let ranges = lines[1..]
@@ -102,145 +63,402 @@ impl FromStr for SeedMap {
     }
 }
 
-#[derive(Debug, Clone)]
-struct SeedMapSet {
-    seeds: Vec<usize>,
-    maps_by_src: HashMap<String, SeedMap>,
-    maps_by_dest: HashMap<String, SeedMap>,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapError {
+    Invalid,
+    Overlap { category: String },
+    NoPath { from: String, to: String },
+    DuplicateSource { category: String },
+    NoSeeds,
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Invalid => write!(f, "invalid almanac"),
+            MapError::Overlap { category } => {
+                write!(f, "{} map has overlapping source ranges", category)
+            }
+            MapError::NoPath { from, to } => write!(f, "no maps lead from {} to {}", from, to),
+            MapError::DuplicateSource { category } => {
+                write!(f, "{} has more than one map", category)
+            }
+            MapError::NoSeeds => write!(f, "no seeds to plant"),
+        }
+    }
+}
+
+impl Error for MapError {}
 
-    _dest_cache: HashMap<(String, usize), usize>,
-    _location_cache: HashMap<usize, usize>,
+/// A run of consecutive values starting at `start` that map to consecutive
+/// values starting at `dest`, or to nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Segment {
+    start: usize,
+    dest: Option<usize>,
 }
 
-impl SeedMapSet {
-    fn new(seeds: Vec<usize>, maps: HashMap<String, SeedMap>) -> Self {
-        let maps_by_dest = maps
-            .clone()
-            .into_iter()
-            .map(|(_k, v)| (v.dest_category.clone(), v))
-            .collect::<HashMap<_, _>>();
+impl Segment {
+    fn get(&self, n: usize) -> Option<usize> {
+        self.dest?.checked_add(n - self.start)
+    }
+}
 
-        SeedMapSet {
-            seeds,
-            maps_by_src: maps,
-            maps_by_dest,
-            _dest_cache: HashMap::new(),
-            _location_cache: HashMap::new(),
+/// Sorts segments and merges neighbours that continue the same line, so
+/// that equal functions have equal segments.
+fn normalize(mut segments: Vec<Segment>) -> Vec<Segment> {
+    segments.sort_by_key(|s| s.start);
+
+    let mut merged: Vec<Segment> = Vec::with_capacity(segments.len());
+
+    for segment in segments {
+        match merged.last() {
+            Some(last) if last.start == segment.start => {
+                *merged.last_mut().unwrap() = segment;
+            }
+            Some(last) if last.get(segment.start) == segment.dest => {}
+            _ => merged.push(segment),
         }
     }
 
-    fn map(&self, src_category: &str, src: usize) -> (String, usize) {
-        let map = self.maps_by_src.get(src_category).unwrap();
+    merged
+}
 
-        let dest = map.map(src);
+fn find_segment(segments: &[Segment], n: usize) -> &Segment {
+    &segments[segments.partition_point(|s| s.start <= n) - 1]
+}
 
-        (map.dest_category.clone(), dest)
-    }
+/// A function between two categories that shifts each of a sorted run of
+/// intervals by its own offset. The segments cover every `usize`, so both
+/// directions are a binary search away.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    segments: Vec<Segment>,
+    inverse: Vec<Segment>,
+}
 
-    fn map_rev(&self, dest_category: &str, dest: usize) -> (String, usize) {
-        let map = self.maps_by_dest.get(dest_category).unwrap();
-        let src = map.map_rev(dest);
+impl PiecewiseMap {
+    fn from_segments(segments: Vec<Segment>) -> Self {
+        let segments = normalize(segments);
+        let inverse = Self::lowest_preimages(&segments);
 
-        (map.source_category.clone(), src)
+        PiecewiseMap { segments, inverse }
     }
 
-    fn map_seed_to_location(&self, src: usize) -> usize {
-        let mut dest = src;
-        let mut category: String = "seed".to_string();
+    fn identity() -> Self {
+        Self::from_segments(vec![Segment {
+            start: 0,
+            dest: Some(0),
+        }])
+    }
+
+    fn from_seed_map(map: &SeedMap) -> Result<Self, MapError> {
+        let mut ranges = map.ranges.iter().collect::<Vec<_>>();
+        ranges.sort_by_key(|r| r.src_range_start);
+
+        let mut segments = vec![Segment {
+            start: 0,
+            dest: Some(0),
+        }];
+        let mut covered_to = 0;
+
+        for range in ranges.into_iter().filter(|r| r.range_length > 0) {
+            if range.src_range_start < covered_to {
+                return Err(MapError::Overlap {
+                    category: map.source_category.clone(),
+                });
+            }
 
-        while category != "location" {
-            let (next_category, next_dest) = self.map(&category, dest);
-            dest = next_dest;
-            category = next_category;
+            // values between ranges map to themselves
+            segments.push(Segment {
+                start: covered_to,
+                dest: Some(covered_to),
+            });
+            segments.push(Segment {
+                start: range.src_range_start,
+                dest: Some(range.dest_range_start),
+            });
+
+            covered_to = range.src_range_start + range.range_length;
         }
 
-        dest
+        segments.push(Segment {
+            start: covered_to,
+            dest: Some(covered_to),
+        });
+
+        Ok(Self::from_segments(segments))
     }
 
-    fn map_seeds_to_locations(&self) -> Vec<usize> {
-        self.seeds
+    /// For every value, the smallest value that maps to it, if any.
+    fn lowest_preimages(segments: &[Segment]) -> Vec<Segment> {
+        // each segment's image, as (first, last, source of first)
+        let images = segments
             .iter()
-            .map(|s| self.map_seed_to_location(*s))
-            .collect()
+            .enumerate()
+            .filter_map(|(i, s)| {
+                let last_src = segments
+                    .get(i + 1)
+                    .map_or(usize::MAX, |next| next.start - 1);
+                let first = s.dest?;
+                let last = first.saturating_add(last_src - s.start);
+
+                Some((first, last, s.start))
+            })
+            .collect::<Vec<_>>();
+
+        let mut cuts = images
+            .iter()
+            .flat_map(|&(first, last, _)| [Some(first), last.checked_add(1)])
+            .flatten()
+            .chain([0])
+            .collect::<Vec<_>>();
+        cuts.sort_unstable();
+        cuts.dedup();
+
+        // images only start or end at cuts, so one source is lowest across
+        // each span between them
+        let inverse = cuts
+            .into_iter()
+            .map(|cut| Segment {
+                start: cut,
+                dest: images
+                    .iter()
+                    .filter(|&&(first, last, _)| first <= cut && cut <= last)
+                    .map(|&(first, _, src)| src + (cut - first))
+                    .min(),
+            })
+            .collect();
+
+        normalize(inverse)
     }
 
-    fn lowest_location(&self) -> usize {
-        self.map_seeds_to_locations().iter().min().unwrap().clone()
+    /// The value `n` maps to.
+    pub fn get(&self, n: usize) -> Option<usize> {
+        find_segment(&self.segments, n).get(n)
     }
 
-    fn map_location_to_seed(&self, dest: usize) -> usize {
-        let mut src = dest;
-        let mut category: String = "location".to_string();
+    /// The smallest value that maps to `n`.
+    pub fn get_rev(&self, n: usize) -> Option<usize> {
+        find_segment(&self.inverse, n).get(n)
+    }
 
-        while category != "seed" {
-            let (next_category, next_src) = self.map_rev(&category, src);
-            src = next_src;
-            category = next_category;
+    /// Applies `self`, then `next`.
+    pub fn then(&self, next: &PiecewiseMap) -> PiecewiseMap {
+        let mut segments = Vec::new();
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            let Some(first) = segment.dest else {
+                segments.push(*segment);
+                continue;
+            };
+
+            let len = self
+                .segments
+                .get(i + 1)
+                .map_or(usize::MAX - segment.start, |next| {
+                    next.start - segment.start - 1
+                });
+            let last = first.saturating_add(len);
+
+            // split the image wherever `next` changes segment
+            let from = next.segments.partition_point(|s| s.start <= first) - 1;
+            for next_segment in next.segments[from..].iter().take_while(|s| s.start <= last) {
+                let at = next_segment.start.max(first);
+
+                segments.push(Segment {
+                    start: segment.start + (at - first),
+                    dest: next_segment.get(at),
+                });
+            }
         }
 
-        src
+        Self::from_segments(segments)
+    }
+
+    /// The smallest value that any of `start..start + len` maps to.
+    fn lowest_image(&self, start: usize, len: usize) -> Option<usize> {
+        let end = start.checked_add(len)?;
+        let from = self
+            .segments
+            .partition_point(|s| s.start <= start)
+            .checked_sub(1)?;
+
+        self.segments[from..]
+            .iter()
+            .take_while(|s| s.start < end)
+            .filter_map(|s| s.get(s.start.max(start)))
+            .min()
     }
+}
 
-    fn lowest_location_from_ranges(&self) -> usize {
-        let mut i = 0usize;
-        let mut ranges: Vec<(usize, usize)> = Vec::new();
+#[derive(Debug, Clone)]
+pub struct SeedMapSet {
+    seeds: Vec<usize>,
+    maps_by_src: HashMap<String, SeedMap>,
+    seed_to_location: PiecewiseMap,
+}
 
-        for range in self.seeds.chunks(2) {
-            let start = range[0];
-            let length = range[1];
+impl SeedMapSet {
+    fn new(seeds: Vec<usize>, maps: Vec<SeedMap>) -> Result<Self, MapError> {
+        let mut maps_by_src = HashMap::new();
+
+        for map in maps {
+            let category = map.source_category.clone();
 
-            ranges.push((start, length));
+            if maps_by_src.insert(category.clone(), map).is_some() {
+                return Err(MapError::DuplicateSource { category });
+            }
         }
 
-        while !ranges
-            .iter()
-            .any(|r| range_contains(r, self.map_location_to_seed(i)))
-        {
-            i += 1;
+        let mut mapset = SeedMapSet {
+            seeds,
+            maps_by_src,
+            seed_to_location: PiecewiseMap::identity(),
+        };
+        mapset.seed_to_location = mapset.map_between("seed", "location")?;
+
+        Ok(mapset)
+    }
+
+    /// The categories leading from `from` to `to`, found by a breadth-first
+    /// search of the category graph.
+    fn path(&self, from: &str, to: &str) -> Result<Vec<&SeedMap>, MapError> {
+        let mut came_by = HashMap::<&str, &SeedMap>::new();
+        let mut seen = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut path = Vec::new();
+                let mut at = to;
+
+                while let Some(map) = came_by.get(at) {
+                    path.push(*map);
+                    at = &map.source_category;
+                }
+                path.reverse();
+
+                return Ok(path);
+            }
+
+            if let Some(map) = self.maps_by_src.get(category) {
+                if seen.insert(&map.dest_category) {
+                    came_by.insert(&map.dest_category, map);
+                    queue.push_back(&map.dest_category);
+                }
+            }
         }
 
-        i
+        Err(MapError::NoPath {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+    }
+
+    /// Composes the maps between two categories into one.
+    pub fn map_between(&self, from: &str, to: &str) -> Result<PiecewiseMap, MapError> {
+        self.path(from, to)?
+            .into_iter()
+            .try_fold(PiecewiseMap::identity(), |composed, map| {
+                Ok(composed.then(&PiecewiseMap::from_seed_map(map)?))
+            })
+    }
+
+    fn map_seed_to_location(&self, src: usize) -> usize {
+        self.seed_to_location.get(src).unwrap_or(usize::MAX)
+    }
+
+    fn map_seeds_to_locations(&self) -> Vec<usize> {
+        self.seeds
+            .iter()
+            .map(|s| self.map_seed_to_location(*s))
+            .collect()
     }
-}
 
-fn range_contains(range: &(usize, usize), n: usize) -> bool {
-    let (start, length) = range;
-    let end = start + length;
+    fn lowest_location(&self) -> Result<usize, MapError> {
+        self.map_seeds_to_locations()
+            .into_iter()
+            .min()
+            .ok_or(MapError::NoSeeds)
+    }
+
+    /// The smallest seed that ends up at `location`, if any does.
+    pub fn seed_for_location(&self, location: usize) -> Option<usize> {
+        self.seed_to_location.get_rev(location)
+    }
 
-    n >= *start && n < end
+    fn lowest_location_from_ranges(&self) -> Result<usize, MapError> {
+        self.seeds
+            .chunks(2)
+            .filter_map(|range| match range {
+                [start, length] => self.seed_to_location.lowest_image(*start, *length),
+                _ => None,
+            })
+            .min()
+            .ok_or(MapError::NoSeeds)
+    }
 }
 
 impl FromStr for SeedMapSet {
-    type Err = ();
+    type Err = MapError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (seeds_str, maps_str) = s.split_once("\n\n").ok_or(())?;
-        let (_, seeds_seq) = seeds_str.split_once(":").ok_or(())?;
-        let seeds = parse_numbers(seeds_seq)?;
+        let invalid = |_| MapError::Invalid;
 
-        let map_sections = maps_str.split("\n\n").collect::<Vec<_>>();
+        let (seeds_str, maps_str) = s.split_once("\n\n").ok_or(MapError::Invalid)?;
+        let (_, seeds_seq) = seeds_str.split_once(":").ok_or(MapError::Invalid)?;
+        let seeds = parse_numbers(seeds_seq).map_err(invalid)?;
 
-        let mut maps = HashMap::new();
-        for map_section in map_sections {
-            let map = map_section.parse::<SeedMap>()?;
-            maps.insert(map.source_category.clone(), map);
-        }
+        let maps = maps_str
+            .split("\n\n")
+            .map(|section| section.parse::<SeedMap>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(invalid)?;
+
+        SeedMapSet::new(seeds, maps)
+    }
+}
 
-        Ok(SeedMapSet::new(seeds, maps))
+fn part1(mapset: &SeedMapSet) -> Result<usize, MapError> {
+    mapset.lowest_location()
+}
+
+fn part2(mapset: &SeedMapSet) -> Result<usize, MapError> {
+    mapset.lowest_location_from_ranges()
+}
+
+pub struct Day5;
+
+impl Solution for Day5 {
+    const DAY: u32 = 5;
+
+    type Input = SeedMapSet;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        Ok(input.parse::<SeedMapSet>()?)
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input)?.into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input)?.into())
     }
 }
 
 #[aoc_generator(day5)]
-fn parse_maps(input: &str) -> SeedMapSet {
-    input.parse().unwrap()
+fn generator(input: &str) -> solution::Result<<Day5 as Solution>::Input> {
+    Day5::parse(input)
 }
 
 #[aoc(day5, part1)]
-fn part1(mapset: &SeedMapSet) -> usize {
-    mapset.lowest_location()
+fn run_part1(input: &<Day5 as Solution>::Input) -> solution::Result<Answer> {
+    Day5::part1(input)
 }
 
 #[aoc(day5, part2)]
-fn part2(mapset: &SeedMapSet) -> usize {
-    mapset.lowest_location_from_ranges()
+fn run_part2(input: &<Day5 as Solution>::Input) -> solution::Result<Answer> {
+    Day5::part2(input)
 }
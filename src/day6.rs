@@ -1,8 +1,9 @@
+use crate::solution::{self, Answer, Solution};
 use crate::util::parse_numbers;
 use aoc_runner_derive::{aoc, aoc_generator};
 
 #[derive(Debug, Clone)]
-struct Game {
+pub struct Game {
     time_limit: usize,
     distance_to_beat: usize,
 }
@@ -54,33 +55,65 @@ impl Game {
     }
 }
 
-#[aoc_generator(day6)]
-fn parse_input(input: &str) -> Vec<Game> {
-    let (times, dists) = input.split_once("\n").unwrap();
-    let time_limits = times.strip_prefix("Time:").unwrap();
-    let distances = dists.strip_prefix("Distance:").unwrap();
+fn parse_input(input: &str) -> solution::Result<Vec<Game>> {
+    let (times, dists) = input.split_once("\n").ok_or("missing distances")?;
+    let time_limits = times.strip_prefix("Time:").ok_or("missing time limits")?;
+    let distances = dists.strip_prefix("Distance:").ok_or("missing distances")?;
 
-    let time_limits = parse_numbers(time_limits).unwrap();
-    let distances = parse_numbers(distances).unwrap();
+    let time_limits = parse_numbers(time_limits).map_err(|_| "invalid time limit")?;
+    let distances = parse_numbers(distances).map_err(|_| "invalid distance")?;
 
-    time_limits
+    Ok(time_limits
         .iter()
         .zip(distances.iter())
         .map(|(t, d)| Game {
             time_limit: *t,
             distance_to_beat: *d,
         })
-        .collect()
+        .collect())
 }
 
-#[aoc(day6, part1)]
 fn part1(games: &[Game]) -> usize {
     games.iter().map(|g| g.count_winning()).product()
 }
 
-#[aoc(day6, part2)]
 fn part2(games: &[Game]) -> usize {
     let game = Game::merge_games(games);
 
     game.count_winning()
 }
+
+pub struct Day6;
+
+impl Solution for Day6 {
+    const DAY: u32 = 6;
+
+    type Input = Vec<Game>;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
+#[aoc_generator(day6)]
+fn generator(input: &str) -> solution::Result<<Day6 as Solution>::Input> {
+    Day6::parse(input)
+}
+
+#[aoc(day6, part1)]
+fn run_part1(input: &<Day6 as Solution>::Input) -> solution::Result<Answer> {
+    Day6::part1(input)
+}
+
+#[aoc(day6, part2)]
+fn run_part2(input: &<Day6 as Solution>::Input) -> solution::Result<Answer> {
+    Day6::part2(input)
+}
@@ -1,45 +1,92 @@
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
+use itertools::Itertools;
 use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::marker::PhantomData;
 use std::str::FromStr;
 
-trait HandTrait {
-    fn get_cards(&self) -> &[usize];
-    fn kind(&self) -> HandKind;
+/// The rules a game of cards is played by: how cards are valued,
+/// which cards are wild, and how hands of the same kind are ordered.
+pub trait RuleSet: Debug + Clone + PartialEq + Eq {
+    fn parse_card(c: char) -> Option<usize>;
+
+    fn is_wildcard(_card: usize) -> bool {
+        false
+    }
+
+    fn tie_break(a: &[usize], b: &[usize]) -> Ordering {
+        a.cmp(b)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Hand {
-    cards: Vec<usize>,
-    bid: usize,
+pub struct CamelCards;
+
+impl RuleSet for CamelCards {
+    fn parse_card(c: char) -> Option<usize> {
+        match c {
+            'A' => Some(14),
+            'K' => Some(13),
+            'Q' => Some(12),
+            'J' => Some(11),
+            'T' => Some(10),
+            '2'..='9' => Some(c as usize - '0' as usize),
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-enum HandKind {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CamelCardsWithJokers;
+
+impl RuleSet for CamelCardsWithJokers {
+    fn parse_card(c: char) -> Option<usize> {
+        match c {
+            'J' => Some(1),
+            _ => CamelCards::parse_card(c),
+        }
+    }
+
+    fn is_wildcard(card: usize) -> bool {
+        card == 1
+    }
+}
+
+/// Hand kinds from weakest to strongest. Camel Cards only deals in rank
+/// multiplicities, so it never produces straights or flushes, and its kinds
+/// keep the same order among themselves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HandKind {
     HighCard,
     OnePair,
     TwoPair,
     ThreeOfAKind,
+    Straight,
+    Flush,
     FullHouse,
     FourOfAKind,
+    StraightFlush,
     FiveOfAKind,
 }
 
-fn hand_kind(cards: &[usize]) -> HandKind {
+fn hand_kind<R: RuleSet>(cards: &[usize]) -> HandKind {
     let mut counts = [0usize; 15];
+    let mut wildcards = 0usize;
 
-    for card in cards {
-        if *card <= 14 {
-            counts[*card] += 1;
+    for &card in cards {
+        if R::is_wildcard(card) {
+            wildcards += 1;
+        } else if card <= 14 {
+            counts[card] += 1;
         }
     }
 
-    let mut counts = counts.to_vec();
-    counts.sort();
+    counts.sort_unstable_by(|a, b| b.cmp(a));
 
-    let mut counts = counts.into_iter().rev();
-
-    let first = counts.next().unwrap();
-    let second = counts.next().unwrap();
+    // a wildcard is always best spent joining the largest group
+    let first = counts[0] + wildcards;
+    let second = counts[1];
 
     if first == 5 {
         HandKind::FiveOfAKind
@@ -58,196 +105,246 @@ fn hand_kind(cards: &[usize]) -> HandKind {
     }
 }
 
-impl HandTrait for Hand {
-    fn get_cards(&self) -> &[usize] {
-        &self.cards
-    }
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
 
-    fn kind(&self) -> HandKind {
-        hand_kind(&self.cards)
+impl Suit {
+    fn parse(c: char) -> Option<Suit> {
+        match c {
+            'c' => Some(Suit::Clubs),
+            'd' => Some(Suit::Diamonds),
+            'h' => Some(Suit::Hearts),
+            's' => Some(Suit::Spades),
+            _ => None,
+        }
     }
 }
 
-fn parse_card(c: char, with_jokers: bool) -> Result<usize, ()> {
-    match c {
-        'A' => Ok(14),
-        'K' => Ok(13),
-        'Q' => Ok(12),
-        'J' if !with_jokers => Ok(11),
-        'J' if with_jokers => Ok(1),
-        'T' => Ok(10),
-        _ => c.to_string().parse().map_err(|_| ()),
-    }
+/// A playing card, with aces high as in Camel Cards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Card {
+    pub rank: usize,
+    pub suit: Suit,
 }
 
-impl FromStr for Hand {
+impl FromStr for Card {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (cards, bid) = s.split_once(' ').ok_or(())?;
-
-        let cards = cards
-            .chars()
-            .map(|c| parse_card(c, false))
-            .collect::<Result<_, _>>()
-            .map_err(|_| ())?;
-
-        let bid = bid.parse().map_err(|_| ())?;
-
-        Ok(Hand { cards, bid })
-    }
-}
-
-fn compare_hands<T: HandTrait>(a: &T, b: &T) -> Ordering {
-    match a.kind().cmp(&b.kind()) {
-        Ordering::Equal => {
-            for (a, b) in a.get_cards().iter().zip(b.get_cards().iter()) {
-                match a.cmp(b) {
-                    Ordering::Equal => continue,
-                    o => return o,
-                }
-            }
-
-            Ordering::Equal
+        let mut chars = s.chars();
+
+        match (chars.next(), chars.next(), chars.next()) {
+            (Some(rank), Some(suit), None) => Ok(Card {
+                rank: CamelCards::parse_card(rank).ok_or(())?,
+                suit: Suit::parse(suit).ok_or(())?,
+            }),
+            _ => Err(()),
         }
-        o => o,
     }
 }
 
-impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> Ordering {
-        compare_hands(self, other)
-    }
-}
+/// Parses cards written rank then suit, such as `AsKhTd9c2s`, ignoring
+/// whitespace between them.
+pub fn parse_cards(s: &str) -> Option<Vec<Card>> {
+    let chars = s.chars().filter(|c| !c.is_whitespace()).collect::<Vec<_>>();
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+    chars
+        .chunks(2)
+        .map(|card| card.iter().collect::<String>().parse().ok())
+        .collect()
 }
 
-#[aoc_generator(day7, part1)]
-fn parse_input(input: &str) -> Vec<Hand> {
-    input.lines().map(|l| l.parse::<Hand>().unwrap()).collect()
+/// A five-card poker hand. Hands order by kind, then by their ranks in
+/// tie-break order: larger groups first, higher ranks first within a group
+/// size, and a straight by its top card.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PokerHand {
+    kind: HandKind,
+    ranks: Vec<usize>,
 }
 
-#[aoc(day7, part1)]
-fn part1(input: &[Hand]) -> usize {
-    let mut hands = input.to_vec();
-
-    hands.sort();
+impl PokerHand {
+    pub fn new(cards: &[Card; 5]) -> Self {
+        let mut ranks = cards.iter().map(|c| c.rank).collect::<Vec<_>>();
+
+        // order by group size, then rank, so that kickers compare in order
+        let count = |rank: usize| ranks.iter().filter(|r| **r == rank).count();
+        let mut grouped = ranks.clone();
+        grouped.sort_unstable_by_key(|r| std::cmp::Reverse((count(*r), *r)));
+        grouped.dedup();
+
+        ranks.sort_unstable();
+        let is_flush = cards.iter().all(|c| c.suit == cards[0].suit);
+        let is_wheel = ranks == [2, 3, 4, 5, 14];
+        let is_straight = grouped.len() == 5 && (ranks[4] - ranks[0] == 4 || is_wheel);
+
+        let top = if is_wheel { 5 } else { ranks[4] };
+
+        match (is_straight, is_flush) {
+            (true, true) => PokerHand {
+                kind: HandKind::StraightFlush,
+                ranks: vec![top],
+            },
+            (true, false) => PokerHand {
+                kind: HandKind::Straight,
+                ranks: vec![top],
+            },
+            (false, true) => PokerHand {
+                kind: HandKind::Flush,
+                ranks: grouped,
+            },
+            (false, false) => PokerHand {
+                kind: hand_kind::<CamelCards>(&ranks),
+                ranks: grouped,
+            },
+        }
+    }
 
-    let mut score = 0usize;
-    for (i, hand) in hands.iter().enumerate() {
-        score += hand.bid * (i + 1);
+    pub fn kind(&self) -> HandKind {
+        self.kind
     }
+}
 
-    score
+/// The best five-card hand among `cards`, such as the seven available to a
+/// player in Texas Hold'em. `None` if there are fewer than five.
+pub fn best_hand(cards: &[Card]) -> Option<PokerHand> {
+    cards
+        .iter()
+        .copied()
+        .combinations(5)
+        .map(|hand| PokerHand::new(&[hand[0], hand[1], hand[2], hand[3], hand[4]]))
+        .max()
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct HandWithJokers {
+pub struct Hand<R: RuleSet> {
     cards: Vec<usize>,
     bid: usize,
     kind: HandKind,
+    rules: PhantomData<R>,
 }
 
-impl HandWithJokers {
-    fn new(cards: Vec<usize>, bid: usize) -> Self {
-        let kind = hand_kind_with_jokers(&cards);
+/// Every Camel Cards hand has exactly this many cards.
+const HAND_SIZE: usize = 5;
 
-        HandWithJokers { cards, bid, kind }
-    }
-}
+impl<R: RuleSet> Hand<R> {
+    fn new(cards: Vec<usize>, bid: usize) -> Self {
+        let kind = hand_kind::<R>(&cards);
 
-fn hand_kind_with_jokers(hand_cards: &[usize]) -> HandKind {
-    let joker_locs = hand_cards
-        .iter()
-        .enumerate()
-        .filter(|(_, c)| **c == 1)
-        .map(|(i, _)| i)
-        .collect::<Vec<_>>();
-
-    let mut cards = hand_cards.to_vec();
-    let mut best_hand = hand_kind(hand_cards);
-
-    // for each joker location, try all possible cards
-    // and find the best hand
-    for joker_loc in &joker_locs {
-        for i in 2..=14 {
-            let old_value = cards[*joker_loc];
-            cards[*joker_loc] = i;
-
-            let hand = hand_kind_with_jokers(&cards);
-            if hand > best_hand {
-                best_hand = hand;
-            }
-
-            cards[*joker_loc] = old_value;
+        Hand {
+            cards,
+            bid,
+            kind,
+            rules: PhantomData,
         }
     }
 
-    best_hand
+    pub fn kind(&self) -> HandKind {
+        self.kind
+    }
 }
 
-impl HandTrait for HandWithJokers {
-    fn get_cards(&self) -> &[usize] {
-        &self.cards
-    }
+impl<R: RuleSet> FromStr for Hand<R> {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (cards, bid) = s.split_once(' ').ok_or(())?;
+
+        let cards = cards
+            .chars()
+            .map(R::parse_card)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(())?;
 
-    fn kind(&self) -> HandKind {
-        self.kind.clone()
+        if cards.len() != HAND_SIZE {
+            return Err(());
+        }
+
+        let bid = bid.parse().map_err(|_| ())?;
+
+        Ok(Hand::new(cards, bid))
     }
 }
 
-impl Ord for HandWithJokers {
+impl<R: RuleSet> Ord for Hand<R> {
     fn cmp(&self, other: &Self) -> Ordering {
-        compare_hands(self, other)
+        match self.kind.cmp(&other.kind) {
+            Ordering::Equal => R::tie_break(&self.cards, &other.cards),
+            o => o,
+        }
     }
 }
 
-impl PartialOrd for HandWithJokers {
+impl<R: RuleSet> PartialOrd for Hand<R> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl FromStr for HandWithJokers {
-    type Err = ();
+fn parse_hands<R: RuleSet>(input: &str) -> solution::Result<Vec<Hand<R>>> {
+    input
+        .lines()
+        .map(|l| l.parse::<Hand<R>>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| "invalid hand".into())
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (cards, bid) = s.split_once(' ').ok_or(())?;
+fn total_winnings<R: RuleSet>(hands: &[Hand<R>]) -> usize {
+    let mut hands = hands.to_vec();
 
-        let cards = cards
-            .chars()
-            .map(|c| parse_card(c, true))
-            .collect::<Result<_, _>>()
-            .map_err(|_| ())?;
+    hands.sort();
 
-        let bid = bid.parse().map_err(|_| ())?;
+    hands
+        .iter()
+        .enumerate()
+        .map(|(i, hand)| hand.bid * (i + 1))
+        .sum()
+}
 
-        Ok(HandWithJokers::new(cards, bid))
-    }
+fn part1(input: &[Hand<CamelCards>]) -> usize {
+    total_winnings(input)
 }
 
-#[aoc_generator(day7, part2)]
-fn parse_input_with_jokers(input: &str) -> Vec<HandWithJokers> {
-    input
-        .lines()
-        .map(|l| l.parse::<HandWithJokers>().unwrap())
-        .collect()
+fn part2(input: &[Hand<CamelCardsWithJokers>]) -> usize {
+    total_winnings(input)
 }
 
-#[aoc(day7, part2)]
-fn part2(input: &[HandWithJokers]) -> usize {
-    let mut hands = input.to_vec();
+pub struct Day7;
 
-    hands.sort();
+impl Solution for Day7 {
+    const DAY: u32 = 7;
 
-    let mut score = 0usize;
-    for (i, hand) in hands.iter().enumerate() {
-        score += hand.bid * (i + 1);
+    type Input = (Vec<Hand<CamelCards>>, Vec<Hand<CamelCardsWithJokers>>);
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        Ok((parse_hands(input)?, parse_hands(input)?))
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(&input.0).into())
     }
 
-    score
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(&input.1).into())
+    }
+}
+
+#[aoc_generator(day7)]
+fn generator(input: &str) -> solution::Result<<Day7 as Solution>::Input> {
+    Day7::parse(input)
+}
+
+#[aoc(day7, part1)]
+fn run_part1(input: &<Day7 as Solution>::Input) -> solution::Result<Answer> {
+    Day7::part1(input)
+}
+
+#[aoc(day7, part2)]
+fn run_part2(input: &<Day7 as Solution>::Input) -> solution::Result<Answer> {
+    Day7::part2(input)
 }
@@ -1,15 +1,30 @@
+use crate::dot::{Digraph, ToDot};
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
 use lazy_static::lazy_static;
+use pathfinding::prelude::strongly_connected_components;
 use regex::Regex;
 use std::collections::HashMap;
 use std::str::FromStr;
+use tracing::{debug, instrument};
 
+#[derive(Debug)]
 enum Direction {
     Left,
     Right,
 }
 
-struct DesertMap {
+impl Direction {
+    fn index(&self) -> usize {
+        match self {
+            Direction::Left => 0,
+            Direction::Right => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DesertMap {
     directions: Vec<Direction>,
     nodes: HashMap<String, (String, String)>,
 }
@@ -39,69 +54,144 @@ impl DesertMap {
         steps
     }
 
-    fn count_steps_and_loop_info(&self, start_node: &str) -> (usize, usize, usize) {
-        let mut steps = 0usize;
-        let mut current_dir_idx = 0usize;
-        let mut current_node = start_node;
-        let mut path = vec![current_node];
-        let mut loop_start = None;
-        let mut loop_length = None;
-        let mut finish = None;
+    /// Indices into `names`, with each node's left and right successors.
+    fn index(&self) -> (Vec<&str>, Vec<[usize; 2]>) {
+        let mut names = self.nodes.keys().map(|k| k.as_str()).collect::<Vec<_>>();
+        names.sort();
 
-        while loop_length.is_none() || finish.is_none() {
-            let (left, right) = &self.nodes[current_node];
+        let index = names
+            .iter()
+            .enumerate()
+            .map(|(i, &name)| (name, i))
+            .collect::<HashMap<_, _>>();
 
-            match self.directions[current_dir_idx] {
-                Direction::Left => {
-                    current_node = left.as_str();
-                }
-                Direction::Right => {
-                    current_node = right.as_str();
-                }
-            }
+        let next = names
+            .iter()
+            .map(|&name| {
+                let (left, right) = &self.nodes[name];
+                [index[left.as_str()], index[right.as_str()]]
+            })
+            .collect();
 
-            current_dir_idx = (current_dir_idx + 1) % self.directions.len();
-            steps += 1;
+        (names, next)
+    }
 
-            if loop_length.is_none() {
-                if path.contains(&current_node) {
-                    loop_start = Some(path.iter().position(|n| n == &current_node).unwrap());
-                    loop_length = Some(path.len() - loop_start.unwrap());
-                } else {
-                    path.push(current_node);
-                }
-            }
+    /// Follows a ghost from every `??A` node through the states
+    /// `(node, instruction index)` until one repeats, which is the only way
+    /// a ghost's path can start looping.
+    #[instrument(level = "debug", skip(self))]
+    pub fn ghost_cycles(&self) -> Vec<GhostCycle> {
+        let (names, next) = self.index();
+        let period = self.directions.len();
 
-            if current_node.ends_with('Z') {
-                finish = Some(steps);
-            }
-        }
+        names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| name.ends_with('A'))
+            .map(|(start, name)| {
+                let mut first_seen = vec![usize::MAX; names.len() * period];
+                let mut path = vec![];
+                let mut node = start;
+
+                let repeated = loop {
+                    let state = node * period + path.len() % period;
 
-        (finish.unwrap(), loop_start.unwrap(), loop_length.unwrap())
+                    if first_seen[state] != usize::MAX {
+                        break state;
+                    }
+
+                    first_seen[state] = path.len();
+                    node = next[node][self.directions[path.len() % period].index()];
+                    path.push(state / period);
+                };
+
+                let tail = first_seen[repeated];
+                let cycle = GhostCycle {
+                    start: name.to_string(),
+                    tail,
+                    cycle: path.len() - tail,
+                    z_offsets: (tail..path.len())
+                        .filter(|&step| names[path[step]].ends_with('Z'))
+                        .collect(),
+                };
+
+                debug!(
+                    start = cycle.start,
+                    cycle.tail,
+                    cycle.cycle,
+                    z_hits = cycle.z_offsets.len(),
+                    "ghost reached its loop"
+                );
+
+                cycle
+            })
+            .collect()
     }
 
-    fn count_steps_in_ghost_traversal(&self) -> usize {
-        let steps = 0usize;
+    /// The strongly connected components of the graph formed by the left and
+    /// right edges, ignoring the instructions. Each component is sorted, and
+    /// components are ordered by their first node.
+    pub fn components(&self) -> Vec<Vec<&str>> {
+        let (names, next) = self.index();
 
-        let start_nodes = self
-            .nodes
-            .iter()
-            .filter(|(k, _)| k.ends_with('A'))
-            .map(|(k, _)| k.as_str())
-            .collect::<Vec<_>>();
-
-        for start_node in start_nodes {
-            println!("Starting at {}", start_node);
-            let (steps_to_finish, loop_start, loop_length) =
-                self.count_steps_and_loop_info(start_node);
-
-            println!(
-                "Steps to finish: {}, loop start: {}, loop length: {}",
-                steps_to_finish, loop_start, loop_length
-            );
+        let mut components =
+            strongly_connected_components(&(0..names.len()).collect::<Vec<_>>(), |&n| next[n])
+                .into_iter()
+                .map(|component| {
+                    let mut component = component.into_iter().map(|n| names[n]).collect::<Vec<_>>();
+                    component.sort();
+                    component
+                })
+                .collect::<Vec<_>>();
+
+        components.sort();
+        components
+    }
+}
+
+/// Where a ghost's path settles into a loop. After `tail` steps it is in a
+/// cycle of `cycle` steps, and it stands on a `??Z` node at step `t` exactly
+/// when `t` is one of `z_offsets` plus a multiple of `cycle`, for
+/// `t >= tail`. Offsets are counted from the start, so all of them fall in
+/// `tail..tail + cycle`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhostCycle {
+    pub start: String,
+    pub tail: usize,
+    pub cycle: usize,
+    pub z_offsets: Vec<usize>,
+}
+
+/// Each node points to where it leads left and right. Ghost start nodes
+/// (`??A`) are green and end nodes (`??Z`) red.
+impl ToDot for DesertMap {
+    fn to_dot(&self) -> String {
+        let mut graph = Digraph::new("desert map");
+        graph.node_defaults(&[("shape", "circle")]);
+
+        let mut names = self.nodes.keys().collect::<Vec<_>>();
+        names.sort();
+
+        for name in &names {
+            if name.ends_with('A') {
+                graph.node(name, &[("style", "filled"), ("fillcolor", "palegreen")]);
+            } else if name.ends_with('Z') {
+                graph.node(name, &[("style", "filled"), ("fillcolor", "salmon")]);
+            }
         }
 
-        steps
+        for name in names {
+            let (left, right) = &self.nodes[name];
+
+            if left == right {
+                graph.edge(name, left, &[("label", "L/R")]);
+            } else {
+                graph.edge(name, left, &[("label", "L")]);
+                graph.edge(name, right, &[("label", "R")]);
+            }
+        }
+
+        graph.finish()
     }
 }
 
@@ -117,44 +207,76 @@ impl FromStr for DesertMap {
         let directions = dirs
             .chars()
             .map(|c| match c {
-                'L' => Direction::Left,
-                'R' => Direction::Right,
-                _ => panic!("Invalid direction"),
+                'L' => Ok(Direction::Left),
+                'R' => Ok(Direction::Right),
+                _ => Err(()),
             })
-            .collect::<Vec<_>>();
+            .collect::<Result<Vec<_>, _>>()?;
 
         let nodes = nodes
             .lines()
             .map(|l| {
                 let (name, rest) = l.split_once(" = ").ok_or(())?;
 
-                let dests = NAME_REGEX
-                    .find_iter(rest)
-                    .map(|m| m.as_str())
-                    .collect::<Vec<_>>();
+                let mut dests = NAME_REGEX.find_iter(rest).map(|m| m.as_str().to_string());
+                let left = dests.next().ok_or(())?;
+                let right = dests.next().ok_or(())?;
 
-                Ok((
-                    name.to_string(),
-                    (dests[0].to_string(), dests[1].to_string()),
-                ))
+                Ok((name.to_string(), (left, right)))
             })
-            .collect::<Result<_, _>>()?;
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        let known = |dest: &String| nodes.contains_key(dest);
+
+        if directions.is_empty() || !nodes.values().all(|(l, r)| known(l) && known(r)) {
+            return Err(());
+        }
 
         Ok(DesertMap { directions, nodes })
     }
 }
 
+fn part1(map: &DesertMap) -> usize {
+    map.count_steps_in_traversal()
+}
+
+fn part2(_map: &DesertMap) -> usize {
+    0
+}
+
+pub struct Day8;
+
+impl Solution for Day8 {
+    const DAY: u32 = 8;
+
+    type Input = DesertMap;
+
+    const STUBS: &'static [u32] = &[2];
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        input.parse().map_err(|_| "invalid desert map".into())
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input).into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input).into())
+    }
+}
+
 #[aoc_generator(day8)]
-fn parse_input(input: &str) -> DesertMap {
-    input.parse().unwrap()
+fn generator(input: &str) -> solution::Result<<Day8 as Solution>::Input> {
+    Day8::parse(input)
 }
 
 #[aoc(day8, part1)]
-fn part1(map: &DesertMap) -> usize {
-    map.count_steps_in_traversal()
+fn run_part1(input: &<Day8 as Solution>::Input) -> solution::Result<Answer> {
+    Day8::part1(input)
 }
 
 #[aoc(day8, part2)]
-fn part2(map: &DesertMap) -> usize {
-    map.count_steps_in_ghost_traversal()
+fn run_part2(input: &<Day8 as Solution>::Input) -> solution::Result<Answer> {
+    Day8::part2(input)
 }
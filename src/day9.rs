@@ -1,53 +1,168 @@
+use crate::solution::{self, Answer, Solution};
 use aoc_runner_derive::{aoc, aoc_generator};
+use std::error::Error;
+use std::fmt;
 
-#[aoc_generator(day9)]
-fn parse_input(input: &str) -> Vec<Vec<isize>> {
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceError {
+    Empty,
+    NotPolynomial,
+    Overflow,
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SequenceError::Empty => write!(f, "empty sequence"),
+            SequenceError::NotPolynomial => write!(f, "differences never reach zero"),
+            SequenceError::Overflow => write!(f, "differences overflow"),
+        }
+    }
+}
+
+impl Error for SequenceError {}
+
+/// A sequence generated by a polynomial, stored as the leading entry of
+/// each row of its difference table.
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    len: usize,
+    leading_diffs: Vec<isize>,
+}
+
+impl Sequence {
+    /// Builds the difference table of `values`, which must run into a row
+    /// of zeros before it runs out of rows: `n` values can pin down a
+    /// polynomial of degree at most `n - 2`.
+    pub fn new(values: &[isize]) -> Result<Self, SequenceError> {
+        if values.is_empty() {
+            return Err(SequenceError::Empty);
+        }
+
+        let mut leading_diffs = Vec::new();
+        let mut row = values.to_vec();
+
+        while !row.iter().all(|v| *v == 0) {
+            if row.len() == 1 {
+                return Err(SequenceError::NotPolynomial);
+            }
+
+            leading_diffs.push(row[0]);
+
+            for i in 0..row.len() - 1 {
+                row[i] = row[i + 1]
+                    .checked_sub(row[i])
+                    .ok_or(SequenceError::Overflow)?;
+            }
+            row.pop();
+        }
+
+        if leading_diffs.is_empty() {
+            leading_diffs.push(0);
+        }
+
+        Ok(Sequence {
+            len: values.len(),
+            leading_diffs,
+        })
+    }
+
+    pub fn degree(&self) -> usize {
+        self.leading_diffs.len() - 1
+    }
+
+    /// The value at index `k`, where index 0 is the first value the
+    /// sequence was built from. Negative indices extrapolate backwards.
+    ///
+    /// Uses Newton's forward difference formula:
+    /// `a(k) = sum_j binomial(k, j) * diff_j(0)`
+    pub fn extrapolate(&self, k: isize) -> Result<isize, SequenceError> {
+        let mut value = 0isize;
+        let mut binomial = 1isize;
+
+        for (j, diff) in self.leading_diffs.iter().enumerate() {
+            value = binomial
+                .checked_mul(*diff)
+                .and_then(|term| value.checked_add(term))
+                .ok_or(SequenceError::Overflow)?;
+
+            // binomial(k, j + 1) = binomial(k, j) * (k - j) / (j + 1), exactly
+            let j = j as isize;
+            binomial = k
+                .checked_sub(j)
+                .and_then(|n| binomial.checked_mul(n))
+                .ok_or(SequenceError::Overflow)?
+                / (j + 1);
+        }
+
+        Ok(value)
+    }
+}
+
+fn parse_input(input: &str) -> solution::Result<Vec<Sequence>> {
     input
         .lines()
         .map(|l| {
-            l.split_whitespace()
-                .map(|n| n.parse::<isize>().unwrap())
-                .collect()
+            let values = l
+                .split_whitespace()
+                .map(|n| n.parse::<isize>())
+                .collect::<Result<Vec<_>, _>>()?;
+
+            Sequence::new(&values).map_err(|e| format!("{}: {}", e, l).into())
         })
-        .collect::<Vec<_>>()
+        .collect()
 }
 
-fn next_number_in_sequence(input: &[isize]) -> isize {
-    let diffs = input.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+fn sum_extrapolated(
+    sequences: &[Sequence],
+    index: impl Fn(&Sequence) -> isize,
+) -> Result<isize, SequenceError> {
+    sequences.iter().try_fold(0isize, |total, s| {
+        total
+            .checked_add(s.extrapolate(index(s))?)
+            .ok_or(SequenceError::Overflow)
+    })
+}
 
-    if diffs.iter().all(|d| *d == 0) {
-        input[input.len() - 1]
-    } else {
-        input[input.len() - 1] + next_number_in_sequence(&diffs)
-    }
+fn part1(sequences: &[Sequence]) -> Result<isize, SequenceError> {
+    sum_extrapolated(sequences, |s| s.len as isize)
 }
 
-fn prev_number_in_sequence(input: &[isize]) -> isize {
-    let diffs = input.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+fn part2(sequences: &[Sequence]) -> Result<isize, SequenceError> {
+    sum_extrapolated(sequences, |_| -1)
+}
+
+pub struct Day9;
+
+impl Solution for Day9 {
+    const DAY: u32 = 9;
 
-    if diffs.iter().all(|d| *d == 0) {
-        input[0]
-    } else {
-        input[0] - prev_number_in_sequence(&diffs)
+    type Input = Vec<Sequence>;
+
+    fn parse(input: &str) -> solution::Result<Self::Input> {
+        parse_input(input)
+    }
+
+    fn part1(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part1(input)?.into())
+    }
+
+    fn part2(input: &Self::Input) -> solution::Result<Answer> {
+        Ok(part2(input)?.into())
     }
 }
 
-#[aoc(day9, part1)]
-fn part1(sequences: &[Vec<isize>]) -> isize {
-    let next_nums = sequences
-        .iter()
-        .map(|s| next_number_in_sequence(s))
-        .collect::<Vec<_>>();
+#[aoc_generator(day9)]
+fn generator(input: &str) -> solution::Result<<Day9 as Solution>::Input> {
+    Day9::parse(input)
+}
 
-    next_nums.iter().sum()
+#[aoc(day9, part1)]
+fn run_part1(input: &<Day9 as Solution>::Input) -> solution::Result<Answer> {
+    Day9::part1(input)
 }
 
 #[aoc(day9, part2)]
-fn part2(sequences: &[Vec<isize>]) -> isize {
-    let prev_nums = sequences
-        .iter()
-        .map(|s| prev_number_in_sequence(s))
-        .collect::<Vec<_>>();
-
-    prev_nums.iter().sum()
+fn run_part2(input: &<Day9 as Solution>::Input) -> solution::Result<Answer> {
+    Day9::part2(input)
 }
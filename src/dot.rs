@@ -0,0 +1,88 @@
+use crate::solution::{self, Solution};
+use crate::{day19, day20, day23, day8};
+
+/// A puzzle input that can be drawn as a graph by Graphviz.
+pub trait ToDot {
+    /// The graph in DOT format.
+    fn to_dot(&self) -> String;
+}
+
+/// Quotes an identifier or label for DOT.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn attributes(attrs: &[(&str, &str)]) -> String {
+    if attrs.is_empty() {
+        return String::new();
+    }
+
+    let attrs = attrs
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, quote(value)))
+        .collect::<Vec<_>>();
+
+    format!(" [{}]", attrs.join(", "))
+}
+
+/// Builds a directed graph in DOT, one statement per line.
+pub(crate) struct Digraph {
+    lines: Vec<String>,
+}
+
+impl Digraph {
+    pub(crate) fn new(name: &str) -> Self {
+        Digraph {
+            lines: vec![format!("digraph {} {{", quote(name))],
+        }
+    }
+
+    /// Sets default attributes for every node.
+    pub(crate) fn node_defaults(&mut self, attrs: &[(&str, &str)]) {
+        self.lines.push(format!("  node{};", attributes(attrs)));
+    }
+
+    pub(crate) fn node(&mut self, id: &str, attrs: &[(&str, &str)]) {
+        self.lines
+            .push(format!("  {}{};", quote(id), attributes(attrs)));
+    }
+
+    pub(crate) fn edge(&mut self, from: &str, to: &str, attrs: &[(&str, &str)]) {
+        self.lines.push(format!(
+            "  {} -> {}{};",
+            quote(from),
+            quote(to),
+            attributes(attrs)
+        ));
+    }
+
+    pub(crate) fn finish(mut self) -> String {
+        self.lines.push("}".to_string());
+
+        self.lines.join("\n") + "\n"
+    }
+}
+
+fn dot_input<S>(input: &str) -> solution::Result<String>
+where
+    S: Solution,
+    S::Input: ToDot,
+{
+    Ok(S::parse(input)?.to_dot())
+}
+
+/// Parses a day's input and draws it as a DOT graph, if that day has one.
+pub fn dot_day(day: u32, input: &str) -> Option<solution::Result<String>> {
+    let dot = match day {
+        8 => dot_input::<day8::Day8>,
+        19 => dot_input::<day19::Day19>,
+        20 => dot_input::<day20::Day20>,
+        23 => dot_input::<day23::Day23>,
+        _ => return None,
+    };
+
+    Some(dot(input))
+}
+
+/// Days that [`dot_day`] can draw.
+pub const DOT_DAYS: &[u32] = &[8, 19, 20, 23];
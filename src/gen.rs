@@ -0,0 +1,1027 @@
+//! Seeded generators for puzzle inputs of any size, for stress-testing the
+//! solvers beyond the one real input per day.
+//!
+//! Every generator writes text in the puzzle's own format, which the day's
+//! parser reads back, and respects the properties the puzzle promises: a
+//! single loop in day 10, a single reflection per pattern in day 13,
+//! acyclic workflows in day 19 and so on.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+
+/// A generator for one day's input, registered in [`GENERATORS`].
+pub struct Generator {
+    pub day: u32,
+    /// A size giving roughly the scale of the real input.
+    pub default_size: usize,
+    generate: fn(&mut StdRng, usize) -> String,
+}
+
+impl Generator {
+    /// Generates an input of the given size; the same seed always gives the
+    /// same input. A size of 0 is taken as 1, since no day's puzzle can be
+    /// empty.
+    pub fn generate(&self, seed: u64, size: usize) -> String {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        (self.generate)(&mut rng, size.max(1))
+    }
+}
+
+macro_rules! generators {
+    ($($day:literal => $generate:ident ($default_size:literal)),* $(,)?) => {
+        /// A generator for every day, in order.
+        pub static GENERATORS: &[Generator] = &[
+            $(Generator { day: $day, default_size: $default_size, generate: $generate },)*
+        ];
+    };
+}
+
+generators! {
+    1 => day1(1000),
+    2 => day2(100),
+    3 => day3(140),
+    4 => day4(200),
+    5 => day5(30),
+    6 => day6(4),
+    7 => day7(1000),
+    8 => day8(750),
+    9 => day9(200),
+    10 => day10(140),
+    11 => day11(140),
+    12 => day12(1000),
+    13 => day13(100),
+    14 => day14(100),
+    15 => day15(4000),
+    16 => day16(110),
+    17 => day17(141),
+    18 => day18(150),
+    19 => day19(500),
+    20 => day20(12),
+    21 => day21(131),
+    22 => day22(1400),
+    23 => day23(6),
+    24 => day24(300),
+}
+
+/// Looks up the generator for a day.
+pub fn generator(day: u32) -> Option<&'static Generator> {
+    GENERATORS.iter().find(|g| g.day == day)
+}
+
+fn lines(lines: impl IntoIterator<Item = String>) -> String {
+    lines.into_iter().collect::<Vec<_>>().join("\n")
+}
+
+fn grid(rows: Vec<Vec<char>>) -> String {
+    lines(rows.into_iter().map(|row| row.into_iter().collect()))
+}
+
+/// `count` distinct names of at least `len` letters drawn from `alphabet`,
+/// none of which is in `reserved`. Names grow longer when `len` letters
+/// can't comfortably fit them all.
+fn names(
+    rng: &mut StdRng,
+    count: usize,
+    len: usize,
+    alphabet: &[u8],
+    reserved: &[&str],
+) -> Vec<String> {
+    let mut seen = reserved
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<HashSet<_>>();
+    let mut names = Vec::with_capacity(count);
+    let len = (len..)
+        .find(|&len| alphabet.len().pow(len as u32) >= 2 * (count + reserved.len()))
+        .unwrap();
+
+    while names.len() < count {
+        let name = (0..len)
+            .map(|_| *alphabet.choose(rng).unwrap() as char)
+            .collect::<String>();
+
+        if seen.insert(name.clone()) {
+            names.push(name);
+        }
+    }
+
+    names
+}
+
+/// The corners of a random simple rectilinear loop filling a `height` by
+/// `width` box, clockwise from the top-left, as `(y, x)`. The loop is
+/// drawn as `columns` side-by-side slabs, each with its own top and bottom,
+/// so every corner turns and horizontal and vertical edges alternate.
+fn rectilinear_loop(
+    rng: &mut StdRng,
+    columns: usize,
+    width: usize,
+    height: usize,
+) -> Vec<(usize, usize)> {
+    assert!(columns >= 1 && width > columns && height >= 4);
+
+    let mut xs = rand::seq::index::sample(rng, width - 2, columns - 1)
+        .into_iter()
+        .map(|x| x + 1)
+        .collect::<Vec<_>>();
+    xs.push(0);
+    xs.push(width - 1);
+    xs.sort();
+
+    let mut slabs: Vec<(usize, usize)> = Vec::with_capacity(columns);
+
+    while slabs.len() < columns {
+        let top = rng.gen_range(0..height - 2);
+        let bottom = rng.gen_range(top + 2..height);
+
+        // neighbouring slabs must overlap so the loop can't pinch, and must
+        // differ so each boundary between them is a real edge
+        let fits = match slabs.last() {
+            None => true,
+            Some(&(prev_top, prev_bottom)) => {
+                top != prev_top
+                    && bottom != prev_bottom
+                    && top.max(prev_top) < bottom.min(prev_bottom)
+            }
+        };
+
+        if fits {
+            slabs.push((top, bottom));
+        }
+    }
+
+    let mut corners = Vec::with_capacity(4 * columns);
+
+    for (i, (top, _)) in slabs.iter().enumerate() {
+        corners.push((*top, xs[i]));
+        corners.push((*top, xs[i + 1]));
+    }
+
+    for (i, (_, bottom)) in slabs.iter().enumerate().rev() {
+        corners.push((*bottom, xs[i + 1]));
+        corners.push((*bottom, xs[i]));
+    }
+
+    corners
+}
+
+/// Calibration lines of letters, digits and spelled-out digits, each with at
+/// least one digit. `size` is the number of lines.
+pub fn day1(rng: &mut StdRng, size: usize) -> String {
+    const WORDS: [&str; 9] = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+
+    lines((0..size).map(|_| {
+        let len = rng.gen_range(5..40);
+        let mut line = String::new();
+
+        while line.len() < len {
+            match rng.gen_range(0..10) {
+                0..=1 => line.push(char::from(b'0' + rng.gen_range(1..10))),
+                2 => line.push_str(WORDS.choose(rng).unwrap()),
+                _ => line.push(char::from(rng.gen_range(b'a'..=b'z'))),
+            }
+        }
+
+        if !line.chars().any(|c| c.is_ascii_digit()) {
+            let at = rng.gen_range(0..=line.len());
+            line.insert(at, char::from(b'0' + rng.gen_range(1..10)));
+        }
+
+        line
+    }))
+}
+
+/// Games of up to six draws of red, green and blue cubes. `size` is the
+/// number of games.
+pub fn day2(rng: &mut StdRng, size: usize) -> String {
+    lines((1..=size).map(|id| {
+        let draws = (0..rng.gen_range(1..=6))
+            .map(|_| {
+                let mut colors = vec!["red", "green", "blue"];
+                colors.shuffle(rng);
+                colors.truncate(rng.gen_range(1..=3));
+
+                colors
+                    .iter()
+                    .map(|color| format!("{} {}", rng.gen_range(1..=20), color))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .collect::<Vec<_>>();
+
+        format!("Game {}: {}", id, draws.join("; "))
+    }))
+}
+
+/// A square engine schematic of part numbers and symbols. `size` is the
+/// side length.
+pub fn day3(rng: &mut StdRng, size: usize) -> String {
+    const SYMBOLS: &[u8] = b"*#+$/@=%-&";
+
+    let mut rows = vec![vec!['.'; size]; size];
+
+    for _ in 0..size * size / 12 {
+        let len = rng.gen_range(1..=3).min(size);
+        let y = rng.gen_range(0..size);
+        let x = rng.gen_range(0..=size - len);
+
+        // numbers on the same row must stay apart or they'd read as one
+        let free = (x.saturating_sub(1)..(x + len + 1).min(size)).all(|x| rows[y][x] == '.');
+
+        if free {
+            let number = rng.gen_range(10usize.pow(len as u32 - 1)..10usize.pow(len as u32));
+
+            for (i, digit) in number.to_string().chars().enumerate() {
+                rows[y][x + i] = digit;
+            }
+        }
+    }
+
+    for _ in 0..size * size / 30 {
+        let (y, x) = (rng.gen_range(0..size), rng.gen_range(0..size));
+
+        if rows[y][x] == '.' {
+            rows[y][x] = *SYMBOLS.choose(rng).unwrap() as char;
+        }
+    }
+
+    grid(rows)
+}
+
+/// Scratchcards of 10 winning numbers and 25 numbers held. `size` is the
+/// number of cards.
+pub fn day4(rng: &mut StdRng, size: usize) -> String {
+    let numbers = |rng: &mut StdRng, n| {
+        rand::seq::index::sample(rng, 99, n)
+            .into_iter()
+            .map(|n| format!("{:>2}", n + 1))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    lines((1..=size).map(|id| {
+        format!(
+            "Card {:>3}: {} | {}",
+            id,
+            numbers(rng, 10),
+            numbers(rng, 25)
+        )
+    }))
+}
+
+/// Ten seed ranges and the seven category maps, each shuffling `size`
+/// ranges of a 32-bit space.
+pub fn day5(rng: &mut StdRng, size: usize) -> String {
+    const CATEGORIES: [&str; 8] = [
+        "seed",
+        "soil",
+        "fertilizer",
+        "water",
+        "light",
+        "temperature",
+        "humidity",
+        "location",
+    ];
+    const SPACE: usize = 1 << 32;
+
+    let seeds = (0..10)
+        .flat_map(|_| {
+            let len = rng.gen_range(1..SPACE / 50);
+            [rng.gen_range(0..SPACE - len), len]
+        })
+        .map(|n| n.to_string())
+        .collect::<Vec<_>>();
+
+    let mut sections = vec![format!("seeds: {}", seeds.join(" "))];
+
+    for pair in CATEGORIES.windows(2) {
+        let mut cuts = rand::seq::index::sample(rng, SPACE - 1, size.max(1) - 1)
+            .into_iter()
+            .map(|c| c + 1)
+            .collect::<Vec<_>>();
+        cuts.push(0);
+        cuts.push(SPACE);
+        cuts.sort();
+
+        let sources = cuts
+            .windows(2)
+            .map(|w| (w[0], w[1] - w[0]))
+            .collect::<Vec<_>>();
+        let mut order = (0..sources.len()).collect::<Vec<_>>();
+        order.shuffle(rng);
+
+        // lay the source ranges end to end in shuffled order to find each
+        // one's destination
+        let mut dests = vec![0; sources.len()];
+        let mut next = 0;
+        for &i in &order {
+            dests[i] = next;
+            next += sources[i].1;
+        }
+
+        let mut ranges = sources
+            .iter()
+            .zip(&dests)
+            .map(|((src, len), dest)| format!("{} {} {}", dest, src, len))
+            .collect::<Vec<_>>();
+        ranges.shuffle(rng);
+
+        sections.push(format!(
+            "{}-to-{} map:\n{}",
+            pair[0],
+            pair[1],
+            ranges.join("\n")
+        ));
+    }
+
+    sections.join("\n\n")
+}
+
+/// Boat races, each with a record that can be beaten. `size` is the number
+/// of races, at most 4 so that part 2's single long race still fits in a
+/// `u64`.
+pub fn day6(rng: &mut StdRng, size: usize) -> String {
+    let races = (0..size.min(4))
+        .map(|_| {
+            let time = rng.gen_range(7..100usize);
+            let best = (time / 2) * (time - time / 2);
+            (time, rng.gen_range(1..best))
+        })
+        .collect::<Vec<_>>();
+
+    let row = |label: &str, values: Vec<usize>| {
+        let values = values
+            .iter()
+            .map(|v| format!("{:>6}", v))
+            .collect::<String>();
+        format!("{:<9}{}", label, values)
+    };
+
+    format!(
+        "{}\n{}",
+        row("Time:", races.iter().map(|r| r.0).collect()),
+        row("Distance:", races.iter().map(|r| r.1).collect())
+    )
+}
+
+/// Camel Cards hands with bids. `size` is the number of hands.
+pub fn day7(rng: &mut StdRng, size: usize) -> String {
+    const CARDS: &[u8] = b"23456789TJQKA";
+
+    lines((0..size).map(|_| {
+        let hand = (0..5)
+            .map(|_| *CARDS.choose(rng).unwrap() as char)
+            .collect::<String>();
+        format!("{} {}", hand, rng.gen_range(1..=1000))
+    }))
+}
+
+/// A desert map of `size` nodes besides AAA and ZZZ. The nodes form a ring
+/// that every step moves one or two places along, and the node before ZZZ
+/// leads only to ZZZ, so every walk reaches it.
+pub fn day8(rng: &mut StdRng, size: usize) -> String {
+    let directions = (0..rng.gen_range(50..300))
+        .map(|_| if rng.gen_bool(0.5) { 'L' } else { 'R' })
+        .collect::<String>();
+
+    let mut ring = names(rng, size, 3, b"ABCDEFGHIJKLMNOPQRSTUVWXYZ", &["AAA", "ZZZ"]);
+    ring.push("ZZZ".to_string());
+    ring.shuffle(rng);
+    ring.insert(0, "AAA".to_string());
+
+    let n = ring.len();
+    let mut nodes = (0..n)
+        .map(|i| {
+            let (next, skip) = (&ring[(i + 1) % n], &ring[(i + 2) % n]);
+
+            let (left, right) = if ring[(i + 1) % n] == "ZZZ" {
+                (next, next)
+            } else {
+                match rng.gen_range(0..3) {
+                    0 => (next, skip),
+                    1 => (skip, next),
+                    _ => (next, next),
+                }
+            };
+
+            format!("{} = ({}, {})", ring[i], left, right)
+        })
+        .collect::<Vec<_>>();
+    nodes.shuffle(rng);
+
+    format!("{}\n\n{}", directions, nodes.join("\n"))
+}
+
+/// Sequences of 21 values of polynomials of degree up to 8. `size` is the
+/// number of sequences.
+pub fn day9(rng: &mut StdRng, size: usize) -> String {
+    const LEN: usize = 21;
+
+    lines((0..size).map(|_| {
+        let degree = rng.gen_range(0..=8);
+
+        // the first value of each row of differences, down to the constant row
+        let mut firsts = (0..degree)
+            .map(|_| rng.gen_range(-10..=10isize))
+            .collect::<Vec<_>>();
+        firsts.push(rng.gen_range(1..=5) * if rng.gen_bool(0.5) { 1 } else { -1 });
+
+        let mut values = Vec::with_capacity(LEN);
+        let mut row = firsts;
+
+        for _ in 0..LEN {
+            values.push(row[0]);
+
+            for i in 0..row.len() - 1 {
+                row[i] += row[i + 1];
+            }
+        }
+
+        values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }))
+}
+
+/// A square field of pipes holding one loop, with junk pipe everywhere
+/// else. `size` is the side length, at least 5.
+pub fn day10(rng: &mut StdRng, size: usize) -> String {
+    const JUNK: &[u8] = b"|-LJ7F..";
+
+    let size = size.max(5);
+    let corners = rectilinear_loop(rng, (size / 4).max(1), size, size);
+
+    let mut path = vec![corners[0]];
+    for i in 0..corners.len() {
+        let (ty, tx) = corners[(i + 1) % corners.len()];
+
+        loop {
+            let &(y, x) = path.last().unwrap();
+            if (y, x) == (ty, tx) {
+                break;
+            }
+
+            path.push((
+                (y as isize + (ty as isize - y as isize).signum()) as usize,
+                (x as isize + (tx as isize - x as isize).signum()) as usize,
+            ));
+        }
+    }
+    path.pop();
+
+    let mut rows = (0..size)
+        .map(|_| {
+            (0..size)
+                .map(|_| *JUNK.choose(rng).unwrap() as char)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let n = path.len();
+    for i in 0..n {
+        let (y, x) = path[i];
+        let mut ends = [path[(i + n - 1) % n], path[(i + 1) % n]];
+        ends.sort();
+
+        let up = (y.wrapping_sub(1), x);
+        let down = (y + 1, x);
+        let left = (y, x.wrapping_sub(1));
+
+        rows[y][x] = match ends {
+            [a, b] if a == up && b == down => '|',
+            [a, b] if a == left && b == (y, x + 1) => '-',
+            [a, b] if a == up && b == (y, x + 1) => 'L',
+            [a, b] if a == up && b == left => 'J',
+            [a, b] if a == left && b == down => '7',
+            _ => 'F',
+        };
+    }
+
+    let (sy, sx) = path[rng.gen_range(0..n)];
+    rows[sy][sx] = 'S';
+
+    // junk next to the start must not look like it connects to it
+    let on_loop = path.iter().collect::<HashSet<_>>();
+    for (dy, dx) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+        let (y, x) = (sy as isize + dy, sx as isize + dx);
+
+        if (0..size as isize).contains(&y)
+            && (0..size as isize).contains(&x)
+            && !on_loop.contains(&(y as usize, x as usize))
+        {
+            rows[y as usize][x as usize] = '.';
+        }
+    }
+
+    grid(rows)
+}
+
+/// A square image of galaxies with some rows and columns left empty. `size`
+/// is the side length.
+pub fn day11(rng: &mut StdRng, size: usize) -> String {
+    let empty_rows = rand::seq::index::sample(rng, size, size / 20).into_vec();
+    let empty_cols = rand::seq::index::sample(rng, size, size / 20).into_vec();
+
+    let mut rows = (0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| {
+                    let blank = empty_rows.contains(&y) || empty_cols.contains(&x);
+                    if !blank && rng.gen_ratio(1, 50) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    // make sure there's at least one pair to measure
+    if size > 1 {
+        rows[0][0] = '#';
+        rows[size - 1][size - 1] = '#';
+    }
+
+    grid(rows)
+}
+
+/// Rows of springs with some conditions unknown, each with the groups of
+/// damaged springs that produced it. `size` is the number of rows.
+pub fn day12(rng: &mut StdRng, size: usize) -> String {
+    lines((0..size).map(|_| {
+        let len = rng.gen_range(5..=20);
+        let mut springs = (0..len)
+            .map(|_| if rng.gen_bool(0.4) { '#' } else { '.' })
+            .collect::<Vec<_>>();
+
+        if !springs.contains(&'#') {
+            springs[rng.gen_range(0..len)] = '#';
+        }
+
+        let groups = springs
+            .split(|c| *c == '.')
+            .filter(|group| !group.is_empty())
+            .map(|group| group.len().to_string())
+            .collect::<Vec<_>>();
+
+        let record = springs
+            .iter()
+            .map(|c| if rng.gen_bool(0.4) { '?' } else { *c })
+            .collect::<String>();
+
+        format!("{} {}", record, groups.join(","))
+    }))
+}
+
+/// Every line of reflection in a pattern, as `(is_horizontal, index)`.
+fn reflections(rows: &[Vec<char>]) -> Vec<(bool, usize)> {
+    let mirrors = |len: usize, same: &dyn Fn(usize, usize) -> bool| {
+        (0..len - 1)
+            .filter(|&i| (0..=i).rev().zip(i + 1..len).all(|(a, b)| same(a, b)))
+            .collect::<Vec<_>>()
+    };
+
+    let (height, width) = (rows.len(), rows[0].len());
+    let horizontal = mirrors(height, &|a, b| rows[a] == rows[b]);
+    let vertical = mirrors(width, &|a, b| rows.iter().all(|row| row[a] == row[b]));
+
+    horizontal
+        .into_iter()
+        .map(|i| (true, i))
+        .chain(vertical.into_iter().map(|i| (false, i)))
+        .collect()
+}
+
+/// Patterns of ash and rocks, each with exactly one line of reflection.
+/// `size` is the number of patterns.
+pub fn day13(rng: &mut StdRng, size: usize) -> String {
+    let patterns = (0..size).map(|_| loop {
+        let (height, width) = (rng.gen_range(5..=17), rng.gen_range(5..=17));
+        let mut rows = (0..height)
+            .map(|_| {
+                (0..width)
+                    .map(|_| if rng.gen_bool(0.5) { '#' } else { '.' })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        if rng.gen_bool(0.5) {
+            let line = rng.gen_range(0..height - 1);
+            for (a, b) in (0..=line).rev().zip(line + 1..height) {
+                rows[b] = rows[a].clone();
+            }
+        } else {
+            let line = rng.gen_range(0..width - 1);
+            for row in rows.iter_mut() {
+                for (a, b) in (0..=line).rev().zip(line + 1..width) {
+                    row[b] = row[a];
+                }
+            }
+        }
+
+        if reflections(&rows).len() == 1 {
+            break grid(rows);
+        }
+    });
+
+    patterns.collect::<Vec<_>>().join("\n\n")
+}
+
+/// A square platform of rounded and cube-shaped rocks. `size` is the side
+/// length.
+pub fn day14(rng: &mut StdRng, size: usize) -> String {
+    grid(
+        (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| match rng.gen_range(0..24) {
+                        0..=3 => 'O',
+                        4..=6 => '#',
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect(),
+    )
+}
+
+/// An initialization sequence of `size` steps over a pool of labels small
+/// enough that lenses get replaced and removed.
+pub fn day15(rng: &mut StdRng, size: usize) -> String {
+    let labels = (0..(size / 4).max(1))
+        .map(|_| {
+            (0..rng.gen_range(1..=6))
+                .map(|_| char::from(rng.gen_range(b'a'..=b'z')))
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+
+    (0..size)
+        .map(|_| {
+            let label = labels.choose(rng).unwrap();
+            if rng.gen_ratio(2, 3) {
+                format!("{}={}", label, rng.gen_range(1..=9))
+            } else {
+                format!("{}-", label)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// A square contraption of mirrors and splitters. `size` is the side length.
+pub fn day16(rng: &mut StdRng, size: usize) -> String {
+    const PIECES: &[u8] = b"|-\\/";
+
+    grid(
+        (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| {
+                        if rng.gen_ratio(1, 10) {
+                            *PIECES.choose(rng).unwrap() as char
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect()
+            })
+            .collect(),
+    )
+}
+
+/// A square map of heat loss per block. `size` is the side length.
+pub fn day17(rng: &mut StdRng, size: usize) -> String {
+    grid(
+        (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| char::from(b'0' + rng.gen_range(1..=9)))
+                    .collect()
+            })
+            .collect(),
+    )
+}
+
+/// The moves around a loop's corners, as `(direction, distance)` with
+/// directions numbered right, down, left, up as in the hex instructions.
+fn loop_moves(corners: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    (0..corners.len())
+        .map(|i| {
+            let (y0, x0) = corners[i];
+            let (y1, x1) = corners[(i + 1) % corners.len()];
+
+            if x1 > x0 {
+                (0, x1 - x0)
+            } else if y1 > y0 {
+                (1, y1 - y0)
+            } else if x1 < x0 {
+                (2, x0 - x1)
+            } else {
+                (3, y0 - y1)
+            }
+        })
+        .collect()
+}
+
+/// A dig plan whose moves and hex instructions each trace their own simple
+/// loop; the hex loop is far larger, as in the real puzzle. `size` is the
+/// number of slabs in each loop, giving four times as many instructions.
+pub fn day18(rng: &mut StdRng, size: usize) -> String {
+    let size = size.max(1);
+
+    let small_width = size * rng.gen_range(2..=8) + 1;
+    let small_height = rng.gen_range(10..=300);
+    // hex distances have five digits, so no edge may reach 0x100000
+    let large_width = (size * rng.gen_range(1_000..=5_000) + 1).min(1_000_000.max(size + 1));
+    let large_height = rng.gen_range(100_000..=1_000_000);
+
+    let small = rectilinear_loop(rng, size, small_width, small_height);
+    let large = rectilinear_loop(rng, size, large_width, large_height);
+
+    lines(loop_moves(&small).into_iter().zip(loop_moves(&large)).map(
+        |((direction, distance), (hex_direction, hex_distance))| {
+            format!(
+                "{} {} (#{:05x}{})",
+                ["R", "D", "L", "U"][direction],
+                distance,
+                hex_distance,
+                hex_direction
+            )
+        },
+    ))
+}
+
+/// A system of `size` workflows and as many parts. Workflows only send
+/// parts to workflows defined after them, so no part can loop, and every
+/// workflow but `in` is sent parts by some earlier one.
+pub fn day19(rng: &mut StdRng, size: usize) -> String {
+    const VARS: [char; 4] = ['x', 'm', 'a', 's'];
+
+    let size = size.max(1);
+    let mut workflows = vec!["in".to_string()];
+    let name_len = rng.gen_range(2..=3);
+    workflows.extend(names(
+        rng,
+        size - 1,
+        name_len,
+        b"abcdefghijklmnopqrstuvwxyz",
+        &["in"],
+    ));
+
+    // each workflow gets a random earlier parent to guarantee it's reachable
+    let mut children = vec![vec![]; size];
+    for child in 1..size {
+        children[rng.gen_range(0..child)].push(child);
+    }
+
+    let definitions = (0..size)
+        .map(|i| {
+            let slots = children[i].len().max(rng.gen_range(2..=5));
+            let mut dests = children[i]
+                .iter()
+                .map(|c| workflows[*c].clone())
+                .collect::<Vec<_>>();
+
+            while dests.len() < slots {
+                dests.push(match rng.gen_range(0..3) {
+                    0 if i + 1 < size => workflows[rng.gen_range(i + 1..size)].clone(),
+                    1 => "R".to_string(),
+                    _ => "A".to_string(),
+                });
+            }
+            dests.shuffle(rng);
+
+            let fallback = dests.pop().unwrap();
+            let rules = dests
+                .iter()
+                .map(|dest| {
+                    format!(
+                        "{}{}{}:{}",
+                        VARS.choose(rng).unwrap(),
+                        if rng.gen_bool(0.5) { '<' } else { '>' },
+                        rng.gen_range(1..=4000),
+                        dest
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            format!("{}{{{},{}}}", workflows[i], rules.join(","), fallback)
+        })
+        .collect::<Vec<_>>();
+
+    let parts = (0..size)
+        .map(|_| {
+            let [x, m, a, s] = [(); 4].map(|_| rng.gen_range(1..=4000));
+            format!("{{x={},m={},a={},s={}}}", x, m, a, s)
+        })
+        .collect::<Vec<_>>();
+
+    format!("{}\n\n{}", definitions.join("\n"), parts.join("\n"))
+}
+
+/// A module configuration built like the real one: four binary counters of
+/// `size` flip-flops each, whose hub conjunctions together send a low pulse
+/// to `rx` once every counter wraps at the same time.
+pub fn day20(rng: &mut StdRng, size: usize) -> String {
+    const COUNTERS: usize = 4;
+
+    let bits = size.clamp(2, 20);
+    let mut pool = names(
+        rng,
+        COUNTERS * (bits + 2) + 1,
+        2,
+        b"abcdefghijklmnopqrstuvwxyz",
+        &["rx"],
+    )
+    .into_iter();
+
+    let final_conjunction = pool.next().unwrap();
+    let mut modules = vec![format!("&{} -> rx", final_conjunction)];
+    let mut starts = vec![];
+
+    for _ in 0..COUNTERS {
+        let flip_flops = pool.by_ref().take(bits).collect::<Vec<_>>();
+        let (hub, inverter) = (pool.next().unwrap(), pool.next().unwrap());
+
+        // the counter resets once it reaches an odd period with the top bit set
+        let period = rng.gen_range(1usize << (bits - 1)..1 << bits) | 1 | 1 << (bits - 1);
+
+        let mut hub_outputs = vec![inverter.clone(), flip_flops[0].clone()];
+
+        for (i, flip_flop) in flip_flops.iter().enumerate() {
+            let mut outputs = vec![];
+
+            if i + 1 < bits {
+                outputs.push(flip_flops[i + 1].clone());
+            }
+
+            if period & (1 << i) != 0 {
+                outputs.push(hub.clone());
+            } else {
+                hub_outputs.push(flip_flop.clone());
+            }
+
+            outputs.shuffle(rng);
+            modules.push(format!("%{} -> {}", flip_flop, outputs.join(", ")));
+        }
+
+        hub_outputs.shuffle(rng);
+        modules.push(format!("&{} -> {}", hub, hub_outputs.join(", ")));
+        modules.push(format!("&{} -> {}", inverter, final_conjunction));
+        starts.push(flip_flops[0].clone());
+    }
+
+    modules.push(format!("broadcaster -> {}", starts.join(", ")));
+    modules.shuffle(rng);
+
+    lines(modules)
+}
+
+/// A square garden with the start in the middle and its row, column and
+/// border clear of rocks. `size` is the side length, rounded up to be odd.
+pub fn day21(rng: &mut StdRng, size: usize) -> String {
+    let size = size.max(5) | 1;
+    let mid = size / 2;
+
+    let mut rows = (0..size)
+        .map(|y| {
+            (0..size)
+                .map(|x| {
+                    let clear =
+                        y == 0 || x == 0 || y == size - 1 || x == size - 1 || y == mid || x == mid;
+                    if !clear && rng.gen_ratio(1, 8) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    rows[mid][mid] = 'S';
+
+    grid(rows)
+}
+
+/// `size` non-overlapping bricks, up to four cubes long, over a 10 by 10
+/// footprint.
+pub fn day22(rng: &mut StdRng, size: usize) -> String {
+    let mut occupied = HashSet::new();
+    let mut bricks = Vec::with_capacity(size);
+    let mut ceiling = 10;
+
+    while bricks.len() < size {
+        let len = rng.gen_range(0..4usize);
+        let axis = rng.gen_range(0..3);
+        let mut start = [
+            rng.gen_range(0..10usize),
+            rng.gen_range(0..10),
+            rng.gen_range(1..ceiling),
+        ];
+        if axis < 2 {
+            start[axis] = start[axis].min(9 - len);
+        }
+        let mut end = start;
+        end[axis] += len;
+
+        let cubes = (0..=len)
+            .map(|i| {
+                let mut cube = start;
+                cube[axis] += i;
+                cube
+            })
+            .collect::<Vec<_>>();
+
+        if cubes.iter().all(|c| !occupied.contains(c)) {
+            occupied.extend(cubes);
+            bricks.push(format!(
+                "{},{},{}~{},{},{}",
+                start[0], start[1], start[2], end[0], end[1], end[2]
+            ));
+        } else {
+            // crowded; give later bricks more room to land
+            ceiling += 1;
+        }
+    }
+
+    lines(bricks)
+}
+
+/// A hiking map of `size` by `size` junctions joined by straight trails,
+/// with slopes on every trail pointing right or down so the hike without
+/// crampons is acyclic.
+pub fn day23(rng: &mut StdRng, size: usize) -> String {
+    let size = size.max(2);
+
+    let spaced = |rng: &mut StdRng, first: usize| {
+        let mut at = vec![first];
+        for _ in 1..size {
+            at.push(at.last().unwrap() + rng.gen_range(4..=12));
+        }
+        at
+    };
+
+    let xs = spaced(rng, 1);
+    let first_y = rng.gen_range(3..=12);
+    let ys = spaced(rng, first_y);
+    let width = xs[size - 1] + 2;
+    let height = ys[size - 1] + rng.gen_range(3..=12);
+
+    let mut rows = vec![vec!['#'; width]; height];
+
+    for row in rows.iter_mut().take(ys[0] + 1) {
+        row[1] = '.';
+    }
+    for row in rows.iter_mut().skip(ys[size - 1]) {
+        row[width - 2] = '.';
+    }
+
+    for &y in &ys {
+        for pair in xs.windows(2) {
+            rows[y][pair[0]..=pair[1]].fill('.');
+            rows[y][pair[0] + 1] = '>';
+            rows[y][pair[1] - 1] = '>';
+        }
+    }
+
+    for &x in &xs {
+        for pair in ys.windows(2) {
+            for row in rows.iter_mut().take(pair[1] + 1).skip(pair[0]) {
+                row[x] = '.';
+            }
+            rows[pair[0] + 1][x] = 'v';
+            rows[pair[1] - 1][x] = 'v';
+        }
+    }
+
+    grid(rows)
+}
+
+/// `size` hailstones that a single thrown rock hits, each at its own
+/// moment, so part 2 has an answer.
+pub fn day24(rng: &mut StdRng, size: usize) -> String {
+    let rock_position = [(); 3].map(|_| rng.gen_range(200_000_000_000_000..400_000_000_000_000i64));
+    let rock_velocity = [(); 3].map(|_| rng.gen_range(-300..=300i64));
+
+    let times = rand::seq::index::sample(rng, 500_000_000_000, size).into_vec();
+
+    lines(times.into_iter().map(|t| {
+        let t = t as i64 + 1;
+        let velocity = [(); 3].map(|_| rng.gen_range(-300..=300i64));
+        let position = [0, 1, 2].map(|i| rock_position[i] + (rock_velocity[i] - velocity[i]) * t);
+
+        format!(
+            "{}, {}, {} @ {}, {}, {}",
+            position[0], position[1], position[2], velocity[0], velocity[1], velocity[2]
+        )
+    }))
+}
@@ -1,29 +1,82 @@
 use aoc_runner_derive::aoc_lib;
 
-mod day1;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day2;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
+pub mod animate;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day2;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod dot;
+pub mod gen;
+pub mod render;
+pub mod solution;
 mod util;
-mod day24;
+
+pub use solution::{solver, Answer, Solution, Solver};
+
+macro_rules! part_number {
+    (part1) => {
+        1
+    };
+    (part2) => {
+        2
+    };
+}
+
+/// Registers the listed parts of each day's [`Solution`] in [`SOLVERS`].
+macro_rules! solutions {
+    ($($day:ident::$solution:ident [$($part:ident),+]),* $(,)?) => {
+        /// Every solved day and part, in order.
+        pub static SOLVERS: &[Solver] = &[
+            $($(Solver::new::<$day::$solution>(part_number!($part)),)+)*
+        ];
+    };
+}
+
+solutions! {
+    day1::Day1 [part1, part2],
+    day2::Day2 [part1, part2],
+    day3::Day3 [part1, part2],
+    day4::Day4 [part1, part2],
+    day5::Day5 [part1, part2],
+    day6::Day6 [part1, part2],
+    day7::Day7 [part1, part2],
+    day8::Day8 [part1, part2],
+    day9::Day9 [part1, part2],
+    day10::Day10 [part1, part2],
+    day11::Day11 [part1, part2],
+    day12::Day12 [part1],
+    day13::Day13 [part1],
+    day14::Day14 [part1, part2],
+    day15::Day15 [part1, part2],
+    day16::Day16 [part1, part2],
+    day17::Day17 [part1, part2],
+    day18::Day18 [part1],
+    day19::Day19 [part1],
+    day20::Day20 [part1, part2],
+    day21::Day21 [part1, part2],
+    day22::Day22 [part1],
+    day23::Day23 [part1, part2],
+    day24::Day24 [part1],
+}
 
 aoc_lib! { year = 2023 }
@@ -1,6 +1,693 @@
 extern crate advent_2023;
-extern crate aoc_runner_derive;
 
-use aoc_runner_derive::aoc_main;
+use advent_2023::animate;
+use advent_2023::dot;
+use advent_2023::gen;
+use advent_2023::render::{self, Style};
+use advent_2023::solution::Error;
+use advent_2023::{Answer, Solver, SOLVERS};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use rayon::prelude::*;
+use std::collections::hash_map::{Entry, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufWriter, IsTerminal, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::process::{self, Child, ExitCode, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
 
-aoc_main! { lib = advent_2023 }
+#[derive(Parser)]
+#[command(about = "Advent of Code 2023 solutions")]
+struct Cli {
+    /// Log solver progress to stderr; filter with RUST_LOG
+    #[arg(long, global = true)]
+    trace: bool,
+
+    /// Like --trace, but log as JSON lines
+    #[arg(long, global = true)]
+    trace_json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run solvers and print their answers with timings
+    Run(RunArgs),
+    /// Run every solver in parallel, each in its own process, and print a
+    /// summary table
+    RunAll(RunAllArgs),
+    /// Run one solver against its bundled input and report to a parent
+    /// `run-all`
+    #[command(hide = true)]
+    Worker(WorkerArgs),
+    /// List every available day and part
+    List,
+    /// Draw a day's grid with its solution highlighted
+    Render(RenderArgs),
+    /// Export the intermediate states of a day's simulation
+    Animate(AnimateArgs),
+    /// Write a day's graph in Graphviz DOT format
+    Dot(DotArgs),
+    /// Print a random input for a day
+    Generate(GenerateArgs),
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// Run every solver against its bundled input
+    #[arg(long, conflicts_with_all = ["day", "part", "input"])]
+    all: bool,
+
+    /// Day to run
+    #[arg(long, required_unless_present = "all")]
+    day: Option<u32>,
+
+    /// Part to run; runs every part of the day when omitted
+    #[arg(long, requires = "day")]
+    part: Option<u32>,
+
+    /// Input file, or `-` for stdin; defaults to input/2023/dayN.txt
+    #[arg(long, requires = "day")]
+    input: Option<String>,
+}
+
+#[derive(Args)]
+struct RunAllArgs {
+    /// Seconds each part may run before it is killed
+    #[arg(long, default_value_t = 30)]
+    timeout: u64,
+
+    /// Number of parts to run at once; defaults to the number of CPUs
+    #[arg(long)]
+    jobs: Option<usize>,
+}
+
+#[derive(Args)]
+struct WorkerArgs {
+    #[arg(long)]
+    day: u32,
+
+    #[arg(long)]
+    part: u32,
+}
+
+#[derive(Args)]
+struct RenderArgs {
+    /// Day to render
+    #[arg(long)]
+    day: u32,
+
+    /// Input file, or `-` for stdin; defaults to input/2023/dayN.txt
+    #[arg(long)]
+    input: Option<String>,
+
+    /// Output style; defaults to ansi when writing to a terminal
+    #[arg(long, value_enum)]
+    style: Option<StyleArg>,
+}
+
+#[derive(Args)]
+struct DotArgs {
+    /// Day to export
+    #[arg(long)]
+    day: u32,
+
+    /// Input file, or `-` for stdin; defaults to input/2023/dayN.txt
+    #[arg(long)]
+    input: Option<String>,
+
+    /// File to write to; defaults to stdout
+    #[arg(long)]
+    output: Option<String>,
+}
+
+#[derive(Args)]
+struct AnimateArgs {
+    /// Day to animate
+    #[arg(long)]
+    day: u32,
+
+    /// Input file, or `-` for stdin; defaults to input/2023/dayN.txt
+    #[arg(long)]
+    input: Option<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    format: FrameFormat,
+
+    /// Style of text frames; defaults to ansi when writing to a terminal
+    #[arg(long, value_enum)]
+    style: Option<StyleArg>,
+
+    /// How long each frame is shown in an SVG or GIF, in milliseconds
+    #[arg(long, default_value_t = 50)]
+    delay: u32,
+
+    /// File to write to; defaults to stdout
+    #[arg(long)]
+    output: Option<String>,
+}
+
+#[derive(Args)]
+struct GenerateArgs {
+    /// Day to generate an input for
+    #[arg(long)]
+    day: u32,
+
+    /// Seed for the random generator
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+
+    /// Scale of the input, such as its line count or side length; defaults
+    /// to roughly that of the real input
+    #[arg(long)]
+    size: Option<usize>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FrameFormat {
+    /// Numbered text frames
+    Text,
+    /// An animated SVG
+    Svg,
+    /// An animated GIF
+    Gif,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StyleArg {
+    /// Coloured with ANSI escape codes
+    Ansi,
+    /// Plain text, with highlights drawn as characters
+    Plain,
+}
+
+/// The stage a solver failed in, and why.
+type Failure = (&'static str, Error);
+
+struct Timings {
+    generator: Duration,
+    runner: Duration,
+}
+
+fn read_input(day: u32, input: Option<&str>) -> Result<String, Error> {
+    let contents = match input {
+        Some("-") => {
+            let mut buf = String::new();
+            std::io::stdin().read_to_string(&mut buf)?;
+            buf
+        }
+        Some(path) => std::fs::read_to_string(path)?,
+        None => std::fs::read_to_string(format!("input/2023/day{}.txt", day))?,
+    };
+
+    Ok(contents.trim_end_matches('\n').to_string())
+}
+
+/// Runs a stage of a solver, turning a panic into an error so that one
+/// broken day can't take down the rest of the run.
+fn catch<T>(stage: &'static str, f: impl FnOnce() -> Result<T, Error>) -> Result<T, Failure> {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result.map_err(|e| (stage, e)),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "panicked".to_string());
+
+            Err((stage, message.into()))
+        }
+    }
+}
+
+fn run_solver(solver: &Solver, input: &str) -> Result<(Answer, Timings), Failure> {
+    let start_time = Instant::now();
+    let parsed = catch("generating", || solver.parse(input))?;
+    let inter_time = Instant::now();
+    let result = catch("running", || solver.solve(parsed.as_ref()))?;
+    let final_time = Instant::now();
+
+    Ok((
+        result,
+        Timings {
+            generator: inter_time - start_time,
+            runner: final_time - inter_time,
+        },
+    ))
+}
+
+fn run(args: &RunArgs) -> Result<bool, Error> {
+    let solvers = SOLVERS
+        .iter()
+        .filter(|s| args.all || Some(s.day) == args.day)
+        .filter(|s| args.part.is_none() || Some(s.part) == args.part)
+        .collect::<Vec<_>>();
+
+    if solvers.is_empty() {
+        return Err(format!(
+            "no solver for day {} part {}",
+            args.day.unwrap_or_default(),
+            args.part.map_or("*".to_string(), |p| p.to_string())
+        )
+        .into());
+    }
+
+    let mut all_ok = true;
+    let mut inputs = HashMap::new();
+
+    for solver in solvers {
+        // read once per day and shared between its parts, since stdin can
+        // only be read once
+        let input = match inputs.entry(solver.day) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(read_input(solver.day, args.input.as_deref())?),
+        };
+
+        match run_solver(solver, input) {
+            Ok((result, timings)) => println!(
+                "Day {} - Part {}: {}\n\tgenerator: {:?},\n\trunner: {:?}\n",
+                solver.day, solver.part, result, timings.generator, timings.runner
+            ),
+            Err((stage, e)) => {
+                all_ok = false;
+                eprintln!(
+                    "Day {} - Part {}: FAILED while {}:\n{}\n",
+                    solver.day, solver.part, stage, e
+                );
+            }
+        }
+    }
+
+    Ok(all_ok)
+}
+
+/// Runs one solver for a parent `run-all`, reporting on stdout as it goes
+/// so that the parent still learns the parse time if solving is killed:
+///
+/// ```text
+/// parsed <nanoseconds>
+/// solved <nanoseconds> <answer>
+/// ```
+///
+/// or `failed <reason>` at any point.
+fn worker(args: &WorkerArgs) {
+    fn report(line: String) {
+        // flushed at once, since the parent reads whatever made it out before a kill
+        let mut out = std::io::stdout();
+        let _ = writeln!(out, "{}", line);
+        let _ = out.flush();
+    }
+
+    let Some(solver) = advent_2023::solver(args.day, args.part) else {
+        report("failed no solver for this part".to_string());
+        return;
+    };
+
+    let steps = || -> Result<(), Failure> {
+        let input = catch("reading input", || read_input(solver.day, None))?;
+
+        let start_time = Instant::now();
+        let parsed = catch("generating", || solver.parse(&input))?;
+        report(format!("parsed {}", start_time.elapsed().as_nanos()));
+
+        let start_time = Instant::now();
+        let answer = catch("running", || solver.solve(parsed.as_ref()))?;
+        report(format!(
+            "solved {} {}",
+            start_time.elapsed().as_nanos(),
+            answer
+        ));
+
+        Ok(())
+    };
+
+    if let Err((stage, e)) = steps() {
+        report(format!("failed while {}: {}", stage, e));
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Stub,
+    Error(String),
+    Timeout,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Ok => f.write_str("ok"),
+            Status::Stub => f.write_str("stub"),
+            Status::Error(_) => f.write_str("error"),
+            Status::Timeout => f.write_str("timeout"),
+        }
+    }
+}
+
+/// What became of one solver in a `run-all`.
+struct Outcome {
+    solver: &'static Solver,
+    status: Status,
+    answer: Option<String>,
+    parse_time: Option<Duration>,
+    solve_time: Option<Duration>,
+}
+
+/// Waits for a child to exit, killing it if it's still running after
+/// `timeout`. Returns `None` if it was killed.
+fn wait_with_timeout(child: &mut Child, timeout: Duration) -> std::io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+
+            return Ok(None);
+        }
+
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Runs a solver in a worker process, so that a part that hangs can be
+/// killed without taking anything else with it.
+fn run_in_worker(solver: &'static Solver, timeout: Duration) -> Outcome {
+    let mut outcome = Outcome {
+        solver,
+        status: Status::Error("worker produced no result".to_string()),
+        answer: None,
+        parse_time: None,
+        solve_time: None,
+    };
+
+    let spawned = std::env::current_exe().and_then(|exe| {
+        process::Command::new(exe)
+            .args(["worker", "--day", &solver.day.to_string()])
+            .args(["--part", &solver.part.to_string()])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+    });
+
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            outcome.status = Status::Error(format!("couldn't start worker: {}", e));
+            return outcome;
+        }
+    };
+
+    // reports are a few short lines, so they fit in the pipe until we read them
+    let exit = wait_with_timeout(&mut child, timeout);
+
+    let mut report = String::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        let _ = stdout.read_to_string(&mut report);
+    }
+
+    for line in report.lines() {
+        let (kind, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let nanos = |s: &str| s.parse().ok().map(Duration::from_nanos);
+
+        match kind {
+            "parsed" => outcome.parse_time = nanos(rest),
+            "solved" => {
+                let (time, answer) = rest.split_once(' ').unwrap_or((rest, ""));
+                outcome.solve_time = nanos(time);
+                outcome.answer = Some(answer.to_string());
+                outcome.status = if solver.stub {
+                    Status::Stub
+                } else {
+                    Status::Ok
+                };
+            }
+            "failed" => outcome.status = Status::Error(rest.to_string()),
+            _ => (),
+        }
+    }
+
+    match exit {
+        Ok(None) => outcome.status = Status::Timeout,
+        Ok(Some(status)) if outcome.answer.is_none() && !status.success() => {
+            outcome.status = Status::Error(format!("worker exited with {}", status));
+        }
+        Ok(Some(_)) => (),
+        Err(e) => outcome.status = Status::Error(format!("couldn't wait for worker: {}", e)),
+    }
+
+    outcome
+}
+
+fn print_table(outcomes: &[Outcome], timeout: Duration) {
+    let rows = outcomes
+        .iter()
+        .map(|o| {
+            let time = |t: Option<Duration>| t.map_or("-".to_string(), |t| format!("{:.2?}", t));
+            let solve_time = match o.status {
+                Status::Timeout => format!(">{:?}", timeout),
+                _ => time(o.solve_time),
+            };
+
+            [
+                o.solver.day.to_string(),
+                o.solver.part.to_string(),
+                o.status.to_string(),
+                o.answer.clone().unwrap_or_else(|| "-".to_string()),
+                time(o.parse_time),
+                solve_time,
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let header = ["day", "part", "status", "answer", "parse", "solve"].map(String::from);
+    let widths = header.each_ref().map(|h| h.len());
+    let widths = rows.iter().fold(widths, |widths, row| {
+        std::array::from_fn(|i| widths[i].max(row[i].chars().count()))
+    });
+
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>();
+
+        println!("{}", cells.join("  ").trim_end());
+    }
+
+    for o in outcomes {
+        if let Status::Error(message) = &o.status {
+            eprintln!(
+                "\nDay {} - Part {}: FAILED {}",
+                o.solver.day, o.solver.part, message
+            );
+        }
+    }
+}
+
+fn run_all(args: &RunAllArgs) -> Result<bool, Error> {
+    let timeout = Duration::from_secs(args.timeout);
+    let mut pool = rayon::ThreadPoolBuilder::new();
+    if let Some(jobs) = args.jobs {
+        pool = pool.num_threads(jobs);
+    }
+
+    let outcomes = pool.build()?.install(|| {
+        SOLVERS
+            .par_iter()
+            .map(|solver| run_in_worker(solver, timeout))
+            .collect::<Vec<_>>()
+    });
+
+    print_table(&outcomes, timeout);
+
+    Ok(outcomes
+        .iter()
+        .all(|o| matches!(o.status, Status::Ok | Status::Stub)))
+}
+
+fn style(arg: Option<StyleArg>, to_terminal: bool) -> Style {
+    match arg {
+        Some(StyleArg::Ansi) => Style::Ansi,
+        Some(StyleArg::Plain) => Style::Plain,
+        None if to_terminal => Style::Ansi,
+        None => Style::Plain,
+    }
+}
+
+fn render(args: &RenderArgs) -> Result<String, Error> {
+    let style = style(args.style, std::io::stdout().is_terminal());
+
+    let input = read_input(args.day, args.input.as_deref())?;
+
+    render::render_day(args.day, &input, style).unwrap_or_else(|| {
+        Err(format!(
+            "day {} can't be rendered; try one of {:?}",
+            args.day,
+            render::RENDERABLE_DAYS
+        )
+        .into())
+    })
+}
+
+fn animate(args: &AnimateArgs) -> Result<(), Error> {
+    let input = read_input(args.day, args.input.as_deref())?;
+
+    let frames = animate::animate_day(args.day, &input).unwrap_or_else(|| {
+        Err(format!(
+            "day {} can't be animated; try one of {:?}",
+            args.day,
+            animate::ANIMATED_DAYS
+        )
+        .into())
+    })?;
+
+    let (mut out, to_terminal): (Box<dyn Write>, bool) = match &args.output {
+        Some(path) => (Box::new(BufWriter::new(File::create(path)?)), false),
+        None => (
+            Box::new(BufWriter::new(std::io::stdout())),
+            std::io::stdout().is_terminal(),
+        ),
+    };
+
+    match args.format {
+        FrameFormat::Text => {
+            animate::write_text(&frames, style(args.style, to_terminal), &mut out)?
+        }
+        FrameFormat::Svg => animate::write_svg(&frames, args.delay, &mut out)?,
+        FrameFormat::Gif => animate::write_gif(&frames, args.delay, &mut out)?,
+    }
+
+    out.flush()?;
+
+    Ok(())
+}
+
+fn dot(args: &DotArgs) -> Result<(), Error> {
+    let input = read_input(args.day, args.input.as_deref())?;
+
+    let graph = dot::dot_day(args.day, &input).unwrap_or_else(|| {
+        Err(format!(
+            "day {} has no graph to export; try one of {:?}",
+            args.day,
+            dot::DOT_DAYS
+        )
+        .into())
+    })?;
+
+    match &args.output {
+        Some(path) => std::fs::write(path, graph)?,
+        None => print!("{}", graph),
+    }
+
+    Ok(())
+}
+
+fn init_tracing(json: bool) {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("advent_2023=debug"));
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr);
+
+    if json {
+        subscriber.json().init();
+    } else {
+        subscriber.init();
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    if cli.trace || cli.trace_json {
+        init_tracing(cli.trace_json);
+    }
+
+    match cli.command {
+        Command::List => {
+            for solver in SOLVERS {
+                let stub = if solver.stub { " (stub)" } else { "" };
+                println!("day {:>2} part {}{}", solver.day, solver.part, stub);
+            }
+
+            ExitCode::SUCCESS
+        }
+        Command::Render(args) => match render(&args) {
+            Ok(rendered) => {
+                print!("{}", rendered);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Command::Generate(args) => match gen::generator(args.day) {
+            Some(generator) => {
+                let size = args.size.unwrap_or(generator.default_size);
+                println!("{}", generator.generate(args.seed, size));
+                ExitCode::SUCCESS
+            }
+            None => {
+                eprintln!("error: no generator for day {}", args.day);
+                ExitCode::FAILURE
+            }
+        },
+        Command::Animate(args) => match animate(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Command::Dot(args) => match dot(&args) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Command::RunAll(args) => match run_all(&args) {
+            Ok(true) => ExitCode::SUCCESS,
+            Ok(false) => ExitCode::FAILURE,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                ExitCode::FAILURE
+            }
+        },
+        Command::Worker(args) => {
+            panic::set_hook(Box::new(|_| {}));
+            worker(&args);
+
+            ExitCode::SUCCESS
+        }
+        Command::Run(args) => {
+            // keep default panic output out of the way; failures are reported per solver
+            panic::set_hook(Box::new(|_| {}));
+
+            match run(&args) {
+                Ok(true) => ExitCode::SUCCESS,
+                Ok(false) => ExitCode::FAILURE,
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+    }
+}
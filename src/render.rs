@@ -0,0 +1,131 @@
+use crate::solution::{self, Solution};
+use crate::{day10, day14, day16, day17, day21, day23};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Highlights are drawn with substitute characters, as in the puzzle text.
+    Plain,
+    /// Highlights are drawn in colour with ANSI escape codes.
+    Ansi,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+impl Color {
+    fn ansi_code(&self) -> u8 {
+        match self {
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+        }
+    }
+}
+
+/// One character of a rendered grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    ch: char,
+    highlight: Option<(Color, char)>,
+}
+
+impl Cell {
+    pub fn new(ch: char) -> Self {
+        Cell {
+            ch,
+            highlight: None,
+        }
+    }
+
+    /// A cell drawn as `ch` in `color` in ANSI output, and as `marker` in
+    /// plain output.
+    pub fn highlighted(ch: char, color: Color, marker: char) -> Self {
+        Cell {
+            ch,
+            highlight: Some((color, marker)),
+        }
+    }
+
+    pub fn ch(&self) -> char {
+        self.ch
+    }
+
+    pub fn color(&self) -> Option<Color> {
+        self.highlight.map(|(color, _)| color)
+    }
+
+    fn write(&self, out: &mut String, style: Style) {
+        match (self.highlight, style) {
+            (None, _) => out.push(self.ch),
+            (Some((_, marker)), Style::Plain) => out.push(marker),
+            (Some((color, _)), Style::Ansi) => {
+                out.push_str(&format!("\x1b[1;{}m{}\x1b[0m", color.ansi_code(), self.ch));
+            }
+        }
+    }
+}
+
+/// Draws rows of cells, one line per row.
+pub fn render_grid<R, C>(rows: R, style: Style) -> String
+where
+    R: IntoIterator<Item = C>,
+    C: IntoIterator<Item = Cell>,
+{
+    let mut out = String::new();
+
+    for row in rows {
+        for cell in row {
+            cell.write(&mut out, style);
+        }
+
+        out.push('\n');
+    }
+
+    out
+}
+
+/// A puzzle state that can be drawn in a terminal.
+pub trait Render {
+    /// The state as rows of cells, top to bottom.
+    fn cells(&self) -> Vec<Vec<Cell>>;
+
+    fn render(&self, style: Style) -> String {
+        render_grid(self.cells(), style)
+    }
+}
+
+fn render_input<S>(input: &str, style: Style) -> solution::Result<String>
+where
+    S: Solution,
+    S::Input: Render,
+{
+    Ok(S::parse(input)?.render(style))
+}
+
+/// Parses a day's input and renders it, if that day can be rendered.
+pub fn render_day(day: u32, input: &str, style: Style) -> Option<solution::Result<String>> {
+    let render = match day {
+        10 => render_input::<day10::Day10>,
+        14 => render_input::<day14::Day14>,
+        16 => render_input::<day16::Day16>,
+        17 => render_input::<day17::Day17>,
+        21 => render_input::<day21::Day21>,
+        23 => render_input::<day23::Day23>,
+        _ => return None,
+    };
+
+    Some(render(input, style))
+}
+
+/// Days that [`render_day`] can draw.
+pub const RENDERABLE_DAYS: &[u32] = &[10, 14, 16, 17, 21, 23];
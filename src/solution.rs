@@ -0,0 +1,132 @@
+use std::any::Any;
+use std::fmt;
+use tracing::info_span;
+
+pub type Error = Box<dyn std::error::Error>;
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// The answer to one part of a puzzle, ready to be printed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Answer(String);
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+macro_rules! answer_from {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(value: $t) -> Self {
+                    Answer(value.to_string())
+                }
+            }
+        )*
+    };
+}
+
+answer_from!(u32, u64, usize, i32, i64, isize, String, &str);
+
+/// One day's puzzle: how to parse its input and how to solve each part.
+pub trait Solution {
+    const DAY: u32;
+
+    type Input: 'static;
+
+    /// Parts whose solver is a placeholder that doesn't solve the puzzle yet.
+    const STUBS: &'static [u32] = &[];
+
+    fn parse(input: &str) -> Result<Self::Input>;
+
+    fn part1(input: &Self::Input) -> Result<Answer>;
+
+    fn part2(_input: &Self::Input) -> Result<Answer> {
+        Err(format!("day {} has no part 2", Self::DAY).into())
+    }
+}
+
+/// A single day and part, with its input type erased so that every
+/// solver can be stored in one registry.
+pub struct Solver {
+    pub day: u32,
+    pub part: u32,
+    /// Whether this part is listed in its solution's [`Solution::STUBS`].
+    pub stub: bool,
+    parse: fn(&str) -> Result<Box<dyn Any>>,
+    solve: fn(&dyn Any) -> Result<Answer>,
+}
+
+fn parse_erased<S: Solution>(input: &str) -> Result<Box<dyn Any>> {
+    Ok(Box::new(S::parse(input)?))
+}
+
+fn input_of<S: Solution>(input: &dyn Any) -> &S::Input {
+    input
+        .downcast_ref()
+        .expect("input was parsed by a different solution")
+}
+
+fn solve_part1<S: Solution>(input: &dyn Any) -> Result<Answer> {
+    S::part1(input_of::<S>(input))
+}
+
+fn solve_part2<S: Solution>(input: &dyn Any) -> Result<Answer> {
+    S::part2(input_of::<S>(input))
+}
+
+const fn contains(parts: &[u32], part: u32) -> bool {
+    let mut i = 0;
+
+    while i < parts.len() {
+        if parts[i] == part {
+            return true;
+        }
+
+        i += 1;
+    }
+
+    false
+}
+
+impl Solver {
+    pub const fn new<S: Solution>(part: u32) -> Self {
+        Solver {
+            day: S::DAY,
+            part,
+            stub: contains(S::STUBS, part),
+            parse: parse_erased::<S>,
+            solve: if part == 1 {
+                solve_part1::<S>
+            } else {
+                solve_part2::<S>
+            },
+        }
+    }
+
+    /// Parses the input into the form this solver expects.
+    pub fn parse(&self, input: &str) -> Result<Box<dyn Any>> {
+        let _span = info_span!("parse", day = self.day).entered();
+
+        (self.parse)(input)
+    }
+
+    /// Solves this part for input produced by [`Solver::parse`].
+    pub fn solve(&self, input: &dyn Any) -> Result<Answer> {
+        let _span = info_span!("solve", day = self.day, part = self.part).entered();
+
+        (self.solve)(input)
+    }
+
+    pub fn run(&self, input: &str) -> Result<Answer> {
+        self.solve(self.parse(input)?.as_ref())
+    }
+}
+
+/// Looks up the solver for a day and part in the registry.
+pub fn solver(day: u32, part: u32) -> Option<&'static Solver> {
+    crate::SOLVERS
+        .iter()
+        .find(|s| s.day == day && s.part == part)
+}
@@ -0,0 +1,1595 @@
+//! Property tests checking the fast solvers against naive reference
+//! implementations on small random instances.
+//!
+//! Each strategy generates the raw description of a puzzle alongside its
+//! parsed input, so the reference implementations here can work from the
+//! description while the solvers work from what they parsed.
+
+use advent_2023::animate::Animate;
+use advent_2023::day11::{Day11, StarMap};
+use advent_2023::day13::{Day13, Pattern};
+use advent_2023::day15::{self, Lens, LensBoxes};
+use advent_2023::day18::{Day18, DigMap};
+use advent_2023::day19::{self, Comparison, Condition, Day19, EvalError, Expr, Part, RuleError};
+use advent_2023::day2::{self, Day2, Game, Hand};
+use advent_2023::day20::{Day20, ModuleConfig};
+use advent_2023::day5::{Day5, SeedMapSet};
+use advent_2023::day7::{
+    best_hand, parse_cards, CamelCards, CamelCardsWithJokers, Card, Hand as CamelHand, HandKind,
+    PokerHand, Suit,
+};
+use advent_2023::day8::{Day8, DesertMap};
+use advent_2023::day9::{Day9, Sequence, SequenceError};
+use advent_2023::render::{self, Style};
+use advent_2023::{gen, Solution};
+use itertools::Itertools;
+use proptest::prelude::*;
+use std::collections::{BTreeMap, HashSet};
+
+fn parse<S: Solution>(text: &str) -> S::Input {
+    S::parse(text).expect("generated input should parse")
+}
+
+// Day 2
+
+/// Counts of red, green and blue cubes.
+type Cubes = (u32, u32, u32);
+
+/// Games of up to four hands, each hand with up to 20 cubes of each colour.
+fn cube_games() -> impl Strategy<Value = (Vec<Vec<Cubes>>, Vec<Game>)> {
+    let hand = (0u32..=20, 0u32..=20, 0u32..=20);
+    let game = prop::collection::vec(hand, 1..4);
+
+    prop::collection::vec(game, 1..8).prop_map(|games| {
+        let text = cube_games_text(&games);
+        let parsed = parse::<Day2>(&text);
+
+        (games, parsed)
+    })
+}
+
+fn cube_games_text(games: &[Vec<Cubes>]) -> String {
+    games
+        .iter()
+        .enumerate()
+        .map(|(i, hands)| {
+            let hands = hands
+                .iter()
+                .map(|(r, g, b)| format!("{} red, {} green, {} blue", r, g, b))
+                .collect::<Vec<_>>()
+                .join("; ");
+            format!("Game {}: {}", i + 1, hands)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn cubes(hand: &Hand) -> Cubes {
+    (hand.red(), hand.green(), hand.blue())
+}
+
+/// The log-likelihood of drawing every hand from `bag`, straight from the
+/// hypergeometric formula.
+fn naive_bag_likelihood(games: &[Vec<Cubes>], bag: Cubes) -> f64 {
+    let ln_choose = |n: u32, k: u32| match k <= n {
+        true => (1..=k).map(|i| ((n - k + i) as f64 / i as f64).ln()).sum(),
+        false => f64::NEG_INFINITY,
+    };
+
+    games
+        .iter()
+        .flatten()
+        .map(|&(r, g, b)| {
+            ln_choose(bag.0, r) + ln_choose(bag.1, g) + ln_choose(bag.2, b)
+                - ln_choose(bag.0 + bag.1 + bag.2, r + g + b)
+        })
+        .sum()
+}
+
+#[test]
+fn day2_sample_bag_inference() {
+    let games = parse::<Day2>(
+        "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green",
+    );
+
+    assert_eq!(cubes(&day2::smallest_bag(&games)), (20, 13, 15));
+
+    let outliers = day2::outliers(&games)
+        .iter()
+        .map(|o| (o.id, cubes(&o.excess)))
+        .collect::<Vec<_>>();
+    assert_eq!(outliers, vec![(3, (6, 10, 0)), (4, (0, 0, 9))]);
+
+    let (bag, likelihood) = day2::most_likely_bag(&games, 40).unwrap();
+    let (red, green, blue) = cubes(&bag);
+    assert!((20..=40).contains(&red) && (13..=40).contains(&green) && (15..=40).contains(&blue));
+    assert!(likelihood.is_finite() && likelihood <= 0.0);
+
+    // no bag with at most 19 cubes of a colour can hold game 3's 20 reds
+    assert_eq!(day2::most_likely_bag(&games, 19), None);
+}
+
+#[test]
+fn day2_most_likely_bag_of_a_small_game() {
+    // From a bag of 2 red and 2 blue cubes, each 3-cube hand leaves out one
+    // of the 4 cubes, so either hand has probability 2/4. From 3 red and 3
+    // blue it is C(3, 2) * C(3, 1) / C(6, 3) = 9/20, and from 3 red and 2
+    // blue the hands have 3/5 and 3/10. Larger bags approach the binomial
+    // 3/8 per hand, so 2 red and 2 blue is best, at (1/2)^2.
+    let games = parse::<Day2>("Game 1: 2 red, 1 blue; 1 red, 2 blue");
+
+    let (bag, likelihood) = day2::most_likely_bag(&games, 30).unwrap();
+    assert_eq!(cubes(&bag), (2, 0, 2));
+    assert!((likelihood - 0.25f64.ln()).abs() < 1e-9);
+
+    // counts far beyond the limit are turned away before any tables are built
+    let games = parse::<Day2>("Game 1: 4000000000 red, 4000000000 blue");
+    assert_eq!(day2::most_likely_bag(&games, 30), None);
+}
+
+// Day 5
+
+/// Seed ranges and a chain of maps, each of which permutes blocks of
+/// `0..space` so that every location has exactly one seed.
+#[derive(Debug, Clone)]
+struct Almanac {
+    seed_ranges: Vec<(usize, usize)>,
+    maps: Vec<Vec<(usize, usize, usize)>>,
+}
+
+impl Almanac {
+    fn text(&self, seeds: &[usize]) -> String {
+        let categories = ["seed", "soil", "fertilizer", "water"][..self.maps.len()]
+            .iter()
+            .chain(&["location"])
+            .collect::<Vec<_>>();
+
+        let seeds = seeds.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let mut text = format!("seeds: {}\n", seeds.join(" "));
+
+        for (i, ranges) in self.maps.iter().enumerate() {
+            text += &format!("\n{}-to-{} map:\n", categories[i], categories[i + 1]);
+
+            for (dest, src, len) in ranges {
+                text += &format!("{} {} {}\n", dest, src, len);
+            }
+        }
+
+        text
+    }
+
+    fn ranged_text(&self) -> String {
+        let seeds = self
+            .seed_ranges
+            .iter()
+            .flat_map(|&(start, len)| [start, len])
+            .collect::<Vec<_>>();
+
+        self.text(&seeds)
+    }
+
+    /// Every seed in every range, listed individually.
+    fn expanded_text(&self) -> String {
+        let seeds = self
+            .seed_ranges
+            .iter()
+            .flat_map(|&(start, len)| start..start + len)
+            .collect::<Vec<_>>();
+
+        self.text(&seeds)
+    }
+}
+
+fn block_permutation(blocks: Vec<usize>) -> impl Strategy<Value = Vec<(usize, usize, usize)>> {
+    let order = (0..blocks.len()).collect::<Vec<_>>();
+
+    Just(order).prop_shuffle().prop_map(move |order| {
+        let starts = blocks
+            .iter()
+            .scan(0, |start, len| {
+                *start += len;
+                Some(*start - len)
+            })
+            .collect::<Vec<_>>();
+
+        let mut dest = 0;
+        let mut ranges = Vec::new();
+
+        for i in order {
+            ranges.push((dest, starts[i], blocks[i]));
+            dest += blocks[i];
+        }
+
+        ranges
+    })
+}
+
+fn almanac() -> impl Strategy<Value = (Almanac, SeedMapSet)> {
+    let map = prop::collection::vec(1usize..20, 1..6).prop_flat_map(block_permutation);
+    let maps = prop::collection::vec(map, 1..=4);
+    let seed_ranges = prop::collection::vec((0usize..120, 1usize..10), 1..4);
+
+    (seed_ranges, maps).prop_map(|(seed_ranges, maps)| {
+        let almanac = Almanac { seed_ranges, maps };
+        let mapset = parse::<Day5>(&almanac.ranged_text());
+
+        (almanac, mapset)
+    })
+}
+
+// Day 7
+
+/// Seven distinct cards, dealt either from a full deck or from a short one
+/// of low cards and aces in two suits, where straights (wheels among them)
+/// and flushes are common.
+fn seven_cards() -> impl Strategy<Value = Vec<Card>> {
+    let deck = |ranks: &[usize], suits: &[Suit]| {
+        ranks
+            .iter()
+            .flat_map(|&rank| suits.iter().map(move |&suit| Card { rank, suit }))
+            .collect::<Vec<_>>()
+    };
+    let all_suits = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+    let full = deck(&(2..=14).collect::<Vec<_>>(), &all_suits);
+    let short = deck(&[2, 3, 4, 5, 6, 13, 14], &[Suit::Clubs, Suit::Hearts]);
+
+    prop_oneof![Just(full).prop_shuffle(), Just(short).prop_shuffle()]
+        .prop_map(|deck| deck[..7].to_vec())
+}
+
+fn five(cards: &[Card]) -> [Card; 5] {
+    [cards[0], cards[1], cards[2], cards[3], cards[4]]
+}
+
+/// Ranks five cards the textbook way: a category from 0 (high card) to 8
+/// (straight flush), then the ranks that break ties within it.
+fn naive_poker_rank(cards: &[Card]) -> (u8, Vec<usize>) {
+    let mut counts = BTreeMap::new();
+    for card in cards {
+        *counts.entry(card.rank).or_insert(0) += 1;
+    }
+
+    let mut groups = counts.iter().map(|(&r, &c)| (c, r)).collect::<Vec<_>>();
+    groups.sort_unstable_by(|a, b| b.cmp(a));
+    let sizes = groups.iter().map(|(c, _)| *c).collect::<Vec<_>>();
+    let ranks = groups.iter().map(|(_, r)| *r).collect::<Vec<_>>();
+
+    let is_flush = cards.iter().all(|c| c.suit == cards[0].suit);
+    // an ace also counts as a one, for the 5-4-3-2-A wheel
+    let has = |rank: usize| counts.contains_key(&rank) || (rank == 1 && counts.contains_key(&14));
+    let straight = (5..=14)
+        .rev()
+        .find(|&top| counts.len() == 5 && (top - 4..=top).all(has));
+
+    match (straight, is_flush, sizes.as_slice()) {
+        (Some(top), true, _) => (8, vec![top]),
+        (_, _, [4, 1]) => (7, ranks),
+        (_, _, [3, 2]) => (6, ranks),
+        (_, true, _) => (5, ranks),
+        (Some(top), _, _) => (4, vec![top]),
+        (_, _, [3, 1, 1]) => (3, ranks),
+        (_, _, [2, 2, 1]) => (2, ranks),
+        (_, _, [2, 1, 1, 1]) => (1, ranks),
+        _ => (0, ranks),
+    }
+}
+
+fn poker_category(kind: HandKind) -> u8 {
+    match kind {
+        HandKind::HighCard => 0,
+        HandKind::OnePair => 1,
+        HandKind::TwoPair => 2,
+        HandKind::ThreeOfAKind => 3,
+        HandKind::Straight => 4,
+        HandKind::Flush => 5,
+        HandKind::FullHouse => 6,
+        HandKind::FourOfAKind => 7,
+        HandKind::StraightFlush => 8,
+        HandKind::FiveOfAKind => unreachable!("five of a kind from one deck"),
+    }
+}
+
+#[test]
+fn day9_rejects_tables_without_a_zero_row() {
+    assert_eq!(
+        Sequence::new(&[1, 2, 4]).unwrap_err(),
+        SequenceError::NotPolynomial
+    );
+    assert_eq!(
+        Sequence::new(&[5]).unwrap_err(),
+        SequenceError::NotPolynomial
+    );
+    assert!(advent_2023::solver(9, 1)
+        .unwrap()
+        .parse("0 3 6\n1 2 4")
+        .is_err());
+
+    assert_eq!(Sequence::new(&[0]).unwrap().extrapolate(1), Ok(0));
+    assert_eq!(Sequence::new(&[7, 7]).unwrap().extrapolate(-1), Ok(7));
+
+    let steep = Sequence::new(&[0, isize::MAX / 2, isize::MAX - 1]).unwrap();
+    assert_eq!(steep.extrapolate(2), Ok(isize::MAX - 1));
+    assert_eq!(steep.extrapolate(3), Err(SequenceError::Overflow));
+}
+
+#[test]
+fn day10_counts_tiles_enclosed_by_the_puzzle_samples() {
+    let solver = advent_2023::solver(10, 2).unwrap();
+    let enclosed = |text: &str| {
+        let map = solver.parse(text).unwrap();
+        solver.solve(map.as_ref()).unwrap().to_string()
+    };
+
+    let squeezed = "\
+...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+...........";
+    assert_eq!(enclosed(squeezed), "4");
+
+    let scattered = "\
+.F----7F7F7F7F-7....
+.|F--7||||||||FJ....
+.||.FJ||||||||L7....
+FJL7L7LJLJ||LJ.L-7..
+L--J.L7...LJS7F-7L7.
+....F-J..F7FJ|L7L7L7
+....L7.F7||L7|.L7L7|
+.....|FJLJ|FJ|F7|.LJ
+....FJL-7.||.||||...
+....L---J.LJ.LJLJ...";
+    assert_eq!(enclosed(scattered), "8");
+
+    let junk = "\
+FF7FSF7F7F7F7F7F---7
+L|LJ||||||||||||F--J
+FL-7LJLJ||||||LJL-77
+F--JF--7||LJLJ7F7FJ-
+L---JF-JLJ.||-FJLJJ7
+|F|F-JF---7F7-L7L|7|
+|FFJF7L7F-JF7|JL---7
+7-L-JL7||F7|L7F-7F7|
+L.L7LFJ|||||FJL7||LJ
+L7JLJL-JLJLJL--JLJ.L";
+    assert_eq!(enclosed(junk), "10");
+}
+
+#[test]
+fn day23_renders_the_longest_hike_on_the_sample() {
+    let sample = "\
+#.#####################
+#.......#########...###
+#######.#########.#.###
+###.....#.>.>.###.#.###
+###v#####.#v#.###.#.###
+###.>...#.#.#.....#...#
+###v###.#.#.#########.#
+###...#.#.#.......#...#
+#####.#.#.#######.#.###
+#.....#.#.#.......#...#
+#.#####.#.#.#########v#
+#.#...#...#...###...>.#
+#.#.#v#######v###.###v#
+#...#.>.#...>.>.#.###.#
+#####v#.#.###v#.#.###.#
+#.....#...#...#.#.#...#
+#.#########.###.#.#.###
+#...###...#...#...#.###
+###.###.#.###v#####v###
+#...#...#.#.>.>.#.>.###
+#.###.###.#.###.#.#v###
+#.....###...###...#...#
+#####################.#";
+    let rendered = render::render_day(23, sample, Style::Plain)
+        .unwrap()
+        .unwrap();
+
+    // the start and each of the 94 steps
+    assert_eq!(rendered.matches('O').count(), 95);
+
+    let rendered = render::render_day(23, "#.#\n###\n#.#", Style::Plain)
+        .unwrap()
+        .unwrap();
+    assert_eq!(rendered, "#.#\n###\n#.#\n");
+}
+
+#[test]
+fn day15_lens_boxes_compare_by_their_lenses() {
+    let lens = |label: &str, focal_length| Lens {
+        label: label.to_string(),
+        focal_length,
+    };
+
+    // all three labels hash to box 0, so removing `aju` leaves a gap
+    let mut boxes = LensBoxes::new();
+    for (label, focal_length) in [("aju", 1), ("akd", 2), ("ayv", 3)] {
+        boxes.insert(lens(label, focal_length));
+    }
+    boxes.remove("aju");
+
+    let mut fresh = LensBoxes::new();
+    fresh.insert(lens("akd", 2));
+    fresh.insert(lens("ayv", 3));
+    assert_eq!(boxes, fresh);
+
+    let mut swapped = LensBoxes::new();
+    swapped.insert(lens("ayv", 3));
+    swapped.insert(lens("akd", 2));
+    assert_ne!(boxes, swapped);
+
+    fresh.insert(lens("akd", 4));
+    assert_ne!(boxes, fresh);
+}
+
+#[test]
+fn day17_rejects_grids_without_blocks() {
+    let solver = advent_2023::solver(17, 1).unwrap();
+
+    for text in ["", "\n", "\n\n", "12\n\n12", "12\n1"] {
+        assert!(solver.parse(text).is_err(), "{:?} parsed", text);
+    }
+
+    let grid = solver.parse("7").unwrap();
+    assert_eq!(solver.solve(grid.as_ref()).unwrap().to_string(), "0");
+}
+
+#[test]
+fn day7_camel_cards_rank_like_the_rules() {
+    let plain = |s: &str| format!("{} 1", s).parse::<CamelHand<CamelCards>>().unwrap();
+    let jokers = |s: &str| {
+        format!("{} 1", s)
+            .parse::<CamelHand<CamelCardsWithJokers>>()
+            .unwrap()
+    };
+
+    assert_eq!(plain("32T3K").kind(), HandKind::OnePair);
+    assert_eq!(plain("KTJJT").kind(), HandKind::TwoPair);
+    assert_eq!(plain("T55J5").kind(), HandKind::ThreeOfAKind);
+    assert_eq!(plain("23332").kind(), HandKind::FullHouse);
+    assert_eq!(plain("AA8AA").kind(), HandKind::FourOfAKind);
+    assert_eq!(plain("AAAAA").kind(), HandKind::FiveOfAKind);
+
+    // same kind, so the first differing card decides
+    assert!(plain("KK677") > plain("KTJJT"));
+    assert!(plain("QQQJA") > plain("T55J5"));
+    assert!(plain("33332") > plain("2AAAA"));
+
+    // a joker joins the largest group, but is the weakest card on a tie
+    assert_eq!(jokers("KTJJT").kind(), HandKind::FourOfAKind);
+    assert_eq!(jokers("T55J5").kind(), HandKind::FourOfAKind);
+    assert_eq!(jokers("2345J").kind(), HandKind::OnePair);
+    assert_eq!(jokers("2233J").kind(), HandKind::FullHouse);
+    assert_eq!(jokers("JJJJJ").kind(), HandKind::FiveOfAKind);
+    assert!(jokers("KTJJT") > jokers("QQQJA"));
+    assert!(jokers("JKKK2") < jokers("QQQQ2"));
+    assert!(jokers("JJJJJ") < jokers("22222"));
+
+    for bad in ["AAAAAA 1", "AAAA 1", "AAAAX 1", "AAAAA", "AAAAA x"] {
+        assert!(
+            bad.parse::<CamelHand<CamelCards>>().is_err(),
+            "{} parsed",
+            bad
+        );
+    }
+}
+
+#[test]
+fn day7_poker_hands_rank_like_the_rules() {
+    let hand = |s: &str| PokerHand::new(&five(&parse_cards(s).unwrap()));
+
+    assert_eq!(
+        parse_cards("AsKh Td9c2s").unwrap(),
+        vec![
+            Card {
+                rank: 14,
+                suit: Suit::Spades
+            },
+            Card {
+                rank: 13,
+                suit: Suit::Hearts
+            },
+            Card {
+                rank: 10,
+                suit: Suit::Diamonds
+            },
+            Card {
+                rank: 9,
+                suit: Suit::Clubs
+            },
+            Card {
+                rank: 2,
+                suit: Suit::Spades
+            },
+        ]
+    );
+    assert_eq!(parse_cards("AsKx"), None);
+    assert_eq!(parse_cards("1s"), None);
+    assert_eq!(parse_cards("AsK"), None);
+
+    // the wheel is the lowest straight, but still beats three of a kind
+    assert_eq!(hand("5d4c3h2sAs").kind(), HandKind::Straight);
+    assert!(hand("5d4c3h2sAs") < hand("6d5c4h3s2s"));
+    assert!(hand("5d4c3h2sAs") > hand("AdAcAh3s2s"));
+    assert_eq!(hand("5s4s3s2sAs").kind(), HandKind::StraightFlush);
+    assert!(hand("5s4s3s2sAs") > hand("AdAcAhAs2s"));
+    assert_eq!(hand("AsKdQhJcTs").kind(), HandKind::Straight);
+    assert_eq!(hand("KsAdQhJc2s").kind(), HandKind::HighCard);
+
+    assert!(hand("9s7s5s3s2s") > hand("AdKcQhJsTs"));
+    assert!(hand("9s9d5s5c5h") > hand("AsKs7s3s2s"));
+
+    // kickers compare from the highest down, after the groups
+    assert!(hand("KsKd9c7h2s") > hand("KhKc9s6d5s"));
+    assert!(hand("QsQd5c5h3s") > hand("JsJd9c9h8s"));
+    assert!(hand("QsQd5c5h4s") > hand("QhQc5s5d3d"));
+    assert_eq!(
+        hand("QsQd5c5h4s").cmp(&hand("QhQc5s5d4d")),
+        std::cmp::Ordering::Equal
+    );
+
+    let seven = parse_cards("AsKs2d3c4h5h9d").unwrap();
+    assert_eq!(best_hand(&seven), Some(hand("As2d3c4h5h")));
+    assert_eq!(best_hand(&seven[..4]), None);
+}
+
+// Day 8
+
+/// A small network with its instructions as `0` for left and `1` for right,
+/// node names, and each node's left and right successors. The first node is
+/// always a ghost start.
+#[derive(Debug, Clone)]
+struct Network {
+    directions: Vec<usize>,
+    names: Vec<String>,
+    next: Vec<[usize; 2]>,
+}
+
+fn network() -> impl Strategy<Value = (Network, DesertMap)> {
+    (1usize..8)
+        .prop_flat_map(|nodes| {
+            (
+                prop::collection::vec(0usize..2, 1..6),
+                prop::collection::vec(prop::sample::select(vec!['A', 'B', 'Z']), nodes),
+                prop::collection::vec([0..nodes, 0..nodes], nodes),
+            )
+        })
+        .prop_map(|(directions, mut suffixes, next)| {
+            suffixes[0] = 'A';
+            let names = suffixes
+                .iter()
+                .enumerate()
+                .map(|(i, suffix)| format!("Q{}{}", i, suffix))
+                .collect::<Vec<_>>();
+
+            let instructions = directions
+                .iter()
+                .map(|d| ['L', 'R'][*d])
+                .collect::<String>();
+            let nodes = names
+                .iter()
+                .zip(&next)
+                .map(|(name, [l, r])| format!("{} = ({}, {})", name, names[*l], names[*r]))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let map = parse::<Day8>(&format!("{}\n\n{}", instructions, nodes));
+
+            (
+                Network {
+                    directions,
+                    names,
+                    next,
+                },
+                map,
+            )
+        })
+}
+
+impl Network {
+    /// The node a ghost from `start` stands on after each step.
+    fn walk(&self, start: usize, steps: usize) -> Vec<usize> {
+        let mut node = start;
+
+        (0..steps)
+            .map(|step| {
+                let here = node;
+                node = self.next[node][self.directions[step % self.directions.len()]];
+                here
+            })
+            .collect()
+    }
+
+    fn reachable(&self) -> Vec<Vec<bool>> {
+        let n = self.names.len();
+        let mut reach = (0..n)
+            .map(|a| {
+                (0..n)
+                    .map(|b| a == b || self.next[a].contains(&b))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        for via in 0..n {
+            for a in 0..n {
+                for b in 0..n {
+                    reach[a][b] |= reach[a][via] && reach[via][b];
+                }
+            }
+        }
+
+        reach
+    }
+}
+
+// Day 9
+
+/// Values of a polynomial with small integer coefficients at consecutive
+/// points, with enough of them to determine it.
+fn polynomial_values() -> impl Strategy<Value = Vec<isize>> {
+    (
+        prop::collection::vec(-5isize..=5, 1..5),
+        -3isize..=3,
+        0usize..6,
+    )
+        .prop_map(|(coefficients, first, extra)| {
+            let len = coefficients.len() + 1 + extra;
+
+            (first..first + len as isize)
+                .map(|x| coefficients.iter().rev().fold(0, |acc, c| acc * x + c))
+                .collect()
+        })
+}
+
+fn sequences() -> impl Strategy<Value = (Vec<Vec<isize>>, Vec<Sequence>)> {
+    prop::collection::vec(polynomial_values(), 1..5).prop_map(|values| {
+        let text = values
+            .iter()
+            .map(|v| {
+                v.iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let sequences = parse::<Day9>(&text);
+
+        (values, sequences)
+    })
+}
+
+fn differences(values: &[isize]) -> Vec<isize> {
+    values.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+fn naive_next(values: &[isize]) -> isize {
+    if values.iter().all(|v| *v == 0) {
+        0
+    } else {
+        values[values.len() - 1] + naive_next(&differences(values))
+    }
+}
+
+/// Whether some row of the difference table is all zeros.
+fn reaches_zero(values: &[isize]) -> bool {
+    !values.is_empty() && (values.iter().all(|v| *v == 0) || reaches_zero(&differences(values)))
+}
+
+fn naive_prev(values: &[isize]) -> isize {
+    if values.iter().all(|v| *v == 0) {
+        0
+    } else {
+        values[0] - naive_prev(&differences(values))
+    }
+}
+
+// Day 11
+
+fn galaxies() -> impl Strategy<Value = (Vec<Vec<bool>>, StarMap)> {
+    (1usize..8, 1usize..8)
+        .prop_flat_map(|(width, height)| {
+            prop::collection::vec(
+                prop::collection::vec(prop::bool::weighted(0.2), width),
+                height,
+            )
+        })
+        .prop_map(|grid| {
+            let text = grid
+                .iter()
+                .map(|row| row.iter().map(|g| if *g { '#' } else { '.' }).collect())
+                .collect::<Vec<String>>()
+                .join("\n");
+            let star_map = parse::<Day11>(&text);
+
+            (grid, star_map)
+        })
+}
+
+/// Widens the grid by literally repeating every empty row and column, then
+/// measures every pair of galaxies.
+fn naive_expanded_distances(grid: &[Vec<bool>], factor: usize) -> usize {
+    let repeat_empty = |rows: Vec<Vec<bool>>| -> Vec<Vec<bool>> {
+        rows.into_iter()
+            .flat_map(|row| {
+                let copies = if row.iter().any(|g| *g) { 1 } else { factor };
+                vec![row; copies]
+            })
+            .collect()
+    };
+    let transpose = |rows: Vec<Vec<bool>>| -> Vec<Vec<bool>> {
+        (0..rows.first().map_or(0, Vec::len))
+            .map(|x| rows.iter().map(|row| row[x]).collect())
+            .collect()
+    };
+
+    let expanded = transpose(repeat_empty(transpose(repeat_empty(grid.to_vec()))));
+
+    let positions = expanded
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, g)| **g)
+                .map(move |(x, _)| (y, x))
+        })
+        .collect::<Vec<_>>();
+
+    let mut total = 0;
+    for (i, a) in positions.iter().enumerate() {
+        for b in &positions[i + 1..] {
+            total += a.0.abs_diff(b.0) + a.1.abs_diff(b.1);
+        }
+    }
+
+    total
+}
+
+// Day 13
+
+/// A random pattern with a reflection forced in by mirroring one side of a
+/// random line onto the other.
+fn mirrored_grid() -> impl Strategy<Value = Vec<Vec<char>>> {
+    (2usize..9, 2usize..9)
+        .prop_flat_map(|(width, height)| {
+            (
+                prop::collection::vec(prop::collection::vec(prop::bool::ANY, width), height),
+                any::<bool>(),
+                0..width - 1,
+                0..height - 1,
+            )
+        })
+        .prop_map(|(grid, vertical, x, y)| {
+            let mut grid = grid
+                .into_iter()
+                .map(|row| row.into_iter().map(|r| if r { '#' } else { '.' }).collect())
+                .collect::<Vec<Vec<char>>>();
+
+            if vertical {
+                for row in grid.iter_mut() {
+                    for offset in 0..=x.min(row.len() - x - 2) {
+                        row[x + 1 + offset] = row[x - offset];
+                    }
+                }
+            } else {
+                for offset in 0..=y.min(grid.len() - y - 2) {
+                    grid[y + 1 + offset] = grid[y - offset].clone();
+                }
+            }
+
+            grid
+        })
+}
+
+fn patterns() -> impl Strategy<Value = (Vec<Vec<Vec<char>>>, Vec<Pattern>)> {
+    prop::collection::vec(mirrored_grid(), 1..5).prop_map(|grids| {
+        let text = grids
+            .iter()
+            .map(|grid| {
+                grid.iter()
+                    .map(|row| row.iter().collect::<String>())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let patterns = parse::<Day13>(&text);
+
+        (grids, patterns)
+    })
+}
+
+fn is_mirrored<T: PartialEq>(items: &[T], line: usize) -> bool {
+    let (before, after) = items.split_at(line + 1);
+
+    before.iter().rev().zip(after).all(|(a, b)| a == b)
+}
+
+/// The first vertical reflection if there is one, otherwise the first
+/// horizontal one, scored as in the puzzle.
+fn naive_reflection_summary(grid: &[Vec<char>]) -> usize {
+    let columns = (0..grid[0].len())
+        .map(|x| grid.iter().map(|row| row[x]).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    if let Some(x) = (0..columns.len() - 1).find(|x| is_mirrored(&columns, *x)) {
+        x + 1
+    } else {
+        let y = (0..grid.len() - 1)
+            .find(|y| is_mirrored(grid, *y))
+            .expect("pattern should have a reflection");
+
+        100 * (y + 1)
+    }
+}
+
+// Day 15
+
+/// Inserts (with a focal length) and removals of labels, several of which
+/// share a box.
+fn lens_steps() -> impl Strategy<Value = Vec<(&'static str, Option<usize>)>> {
+    let label = prop::sample::select(vec!["rn", "cm", "qp", "pc", "ot", "ab"]);
+
+    prop::collection::vec((label, prop::option::of(1usize..=9)), 0..60)
+}
+
+// Day 18
+
+/// The outline of a run of side-by-side columns that all share row 5, given
+/// as each column's width and vertical extent. Optionally walked the other
+/// way round.
+fn trench() -> impl Strategy<Value = (String, DigMap)> {
+    let column = (1isize..4, 0isize..=4, 5isize..=9);
+
+    (prop::collection::vec(column, 1..6), any::<bool>()).prop_map(|(columns, reversed)| {
+        let vertical = |from: isize, to: isize| match to - from {
+            0 => None,
+            d if d > 0 => Some(('U', d)),
+            d => Some(('D', -d)),
+        };
+
+        let mut moves = Vec::new();
+        for (i, (width, _, top)) in columns.iter().enumerate() {
+            moves.push(('R', *width));
+            if let Some((_, _, next_top)) = columns.get(i + 1) {
+                moves.extend(vertical(*top, *next_top));
+            }
+        }
+
+        let (_, last_bottom, last_top) = columns[columns.len() - 1];
+        moves.push(('D', last_top - last_bottom));
+
+        for (i, (width, bottom, _)) in columns.iter().enumerate().rev() {
+            moves.push(('L', *width));
+            if i > 0 {
+                moves.extend(vertical(*bottom, columns[i - 1].1));
+            }
+        }
+
+        let (_, first_bottom, first_top) = columns[0];
+        moves.push(('U', first_top - first_bottom));
+
+        if reversed {
+            moves.reverse();
+            for (direction, _) in moves.iter_mut() {
+                *direction = match direction {
+                    'U' => 'D',
+                    'D' => 'U',
+                    'L' => 'R',
+                    _ => 'L',
+                };
+            }
+        }
+
+        let text = moves
+            .iter()
+            .map(|(direction, distance)| format!("{} {} (#000000)", direction, distance))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let dig_map = parse::<Day18>(&text);
+
+        (text, dig_map)
+    })
+}
+
+// Day 19
+
+fn parts() -> impl Strategy<Value = Vec<Part>> {
+    let part = prop::collection::vec(1i64..=4000, 4).prop_map(Part::new);
+
+    prop::collection::vec(part, 1..50)
+}
+
+/// Up to five workflows, `w0` being `in`, each of which sends parts with a
+/// high enough `x` one way and the rest another. Workflows may send parts
+/// back to earlier ones, or to themselves.
+fn looping_workflows() -> impl Strategy<Value = Vec<(i64, usize, usize)>> {
+    (1usize..=5).prop_flat_map(|n| {
+        // destinations past the last workflow are `A` and `R`
+        let workflow = (1i64..=4000, 0..n + 2, 0..n + 2);
+        prop::collection::vec(workflow, n)
+    })
+}
+
+fn looping_workflows_text(workflows: &[(i64, usize, usize)]) -> String {
+    let name = |i: usize| match i {
+        0 => "in".to_string(),
+        i if i < workflows.len() => format!("w{}", i),
+        i if i == workflows.len() => "A".to_string(),
+        _ => "R".to_string(),
+    };
+
+    let lines = workflows
+        .iter()
+        .enumerate()
+        .map(|(i, (above, high, low))| {
+            format!("{}{{x>{}:{},{}}}", name(i), above, name(*high), name(*low))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    format!("{}\n\n{{x=1,m=1,a=1,s=1}}", lines)
+}
+
+/// Follows a part through the workflows, remembering each one it has been
+/// through.
+fn naive_looping_accepts(workflows: &[(i64, usize, usize)], x: i64) -> Result<bool, EvalError> {
+    let mut seen = HashSet::new();
+    let mut workflow = 0;
+
+    while workflow < workflows.len() {
+        if !seen.insert(workflow) {
+            return Err(EvalError::Cycle);
+        }
+
+        let (above, high, low) = workflows[workflow];
+        workflow = if x > above { high } else { low };
+    }
+
+    Ok(workflow == workflows.len())
+}
+
+/// Arithmetic on the `xmas` ratings. Numbers are never negative, since the
+/// rule language has no negative literals.
+fn expr() -> impl Strategy<Value = Expr> {
+    let leaf = prop_oneof![
+        (0i64..5000).prop_map(Expr::Number),
+        prop::sample::select(vec!["x", "m", "a", "s"]).prop_map(|f| Expr::Field(f.to_string())),
+    ];
+
+    leaf.prop_recursive(3, 12, 2, |inner| {
+        (inner.clone(), inner, 0..3).prop_map(|(l, r, op)| {
+            let (l, r) = (Box::new(l), Box::new(r));
+            match op {
+                0 => Expr::Add(l, r),
+                1 => Expr::Sub(l, r),
+                _ => Expr::Mul(l, r),
+            }
+        })
+    })
+}
+
+fn condition() -> impl Strategy<Value = Condition> {
+    let comparison = prop::sample::select(vec![
+        Comparison::Less,
+        Comparison::LessEqual,
+        Comparison::Equal,
+        Comparison::NotEqual,
+        Comparison::GreaterEqual,
+        Comparison::Greater,
+    ]);
+    let leaf = (expr(), comparison, expr()).prop_map(|(l, cmp, r)| Condition::Compare(l, cmp, r));
+
+    leaf.prop_recursive(3, 8, 2, |inner| {
+        (inner.clone(), inner, any::<bool>()).prop_map(|(l, r, and)| {
+            let (l, r) = (Box::new(l), Box::new(r));
+            match and {
+                true => Condition::And(l, r),
+                false => Condition::Or(l, r),
+            }
+        })
+    })
+}
+
+#[test]
+fn day19_deep_rules_are_refused() {
+    let nested = |depth: usize| format!("{}x>1{}", "(".repeat(depth), ")".repeat(depth));
+
+    assert!(nested(day19::MAX_NESTING).parse::<Condition>().is_ok());
+    assert_eq!(
+        nested(day19::MAX_NESTING + 1).parse::<Condition>(),
+        Err(RuleError::TooDeeplyNested)
+    );
+    assert_eq!(
+        nested(200_000).parse::<Condition>(),
+        Err(RuleError::TooLong)
+    );
+
+    let chain = format!("{}x>1", "x+".repeat(day19::MAX_TOKENS));
+    assert_eq!(chain.parse::<Condition>(), Err(RuleError::TooLong));
+}
+
+#[test]
+fn day19_workflows_that_loop_or_repeat_are_refused() {
+    let solver = advent_2023::solver(19, 1).unwrap();
+    let accepted = |text: &str| {
+        let system = solver.parse(text).unwrap();
+        solver
+            .solve(system.as_ref())
+            .map(|answer| answer.to_string())
+    };
+
+    assert!(accepted("in{a}\na{in}\n\n{x=1}").is_err());
+    assert!(accepted("in{in}\n\n{x=1}").is_err());
+    assert!(accepted("in{x>5:a,A}\na{in}\n\n{x=9}").is_err());
+    assert_eq!(accepted("in{x>5:a,A}\na{in}\n\n{x=1}").unwrap(), "1");
+
+    assert_eq!(
+        "in{A}\nin{R}\n\n{x=1}".parse::<day19::System>().err(),
+        Some(RuleError::DuplicateWorkflow("in".to_string()))
+    );
+}
+
+#[test]
+fn day19_rating_sums_past_i64_are_refused() {
+    let system = parse::<Day19>("in{A}\n\n{x=9223372036854775807,m=1}");
+    assert_eq!(system.parts()[0].rating_number(), Err(EvalError::Overflow));
+    assert!(Day19::part1(&system).is_err());
+
+    let system = parse::<Day19>("in{A}\n\n{x=9223372036854775806,m=1}\n{x=1,m=0}");
+    assert_eq!(system.parts()[0].rating_number(), Ok(i64::MAX));
+    assert!(Day19::part1(&system).is_err());
+}
+
+// Day 20
+
+/// A small random circuit of every kind of module that keeps state. Each
+/// module only sends to later ones or to a sink, so every press ends. A few
+/// conjunctions have no inputs at all, and so no bits of state.
+fn circuit() -> impl Strategy<Value = ModuleConfig> {
+    let kind = prop::sample::select(vec!['%', '&', '!', '/', '~']);
+    let module = (kind, prop::collection::vec(0usize..8, 1..3));
+
+    (
+        prop::collection::vec(module, 1..8),
+        prop::collection::vec(0usize..8, 1..3),
+        0usize..3,
+    )
+        .prop_map(|(modules, starts, unfed)| {
+            let name = |i: usize| match i < modules.len() {
+                true => format!("m{}", i),
+                false => "out".to_string(),
+            };
+            let list = |ids: Vec<usize>| ids.into_iter().map(name).collect::<Vec<_>>().join(", ");
+
+            let mut lines = vec![format!("broadcaster -> {}", list(starts))];
+            for (i, (kind, outputs)) in modules.iter().enumerate() {
+                let later = outputs.iter().map(|o| i + 1 + o).collect();
+                lines.push(format!("{}{} -> {}", kind, name(i), list(later)));
+            }
+            for i in 0..unfed {
+                lines.push(format!("&unfed{} -> {}", i, name(0)));
+            }
+
+            parse::<Day20>(&lines.join("\n"))
+        })
+}
+
+#[test]
+fn day20_pulse_score_of_many_presses() {
+    // a flip-flop on every other press: five lows and a high per two presses
+    let flipper = parse::<Day20>("broadcaster -> a\n%a -> out");
+
+    assert_eq!(
+        flipper.pulse_score(2_000_000_000),
+        Some(5_000_000_000 * 1_000_000_000)
+    );
+    assert_eq!(flipper.pulse_score(10_000_000_000), None);
+    assert_eq!(flipper.pulse_score(usize::MAX), None);
+}
+
+#[test]
+fn day20_part2_needs_an_rx_module() {
+    let without_rx = parse::<Day20>("broadcaster -> a\n%a -> b\n&b -> out");
+    assert!(Day20::part2(&without_rx).is_err());
+
+    let with_rx = parse::<Day20>("broadcaster -> a\n%a -> b\n&b -> rx");
+    assert_eq!(Day20::part2(&with_rx).unwrap().to_string(), "1");
+
+    // the wiring is shared between clones, so clones can go to other threads
+    fn shareable<T: Send + Sync>(_: &T) {}
+    shareable(&with_rx);
+}
+
+// Day 21
+
+/// Rows of gardens and rocks with the start in the top left corner. The
+/// rows may be ragged.
+fn garden_rows() -> impl Strategy<Value = Vec<String>> {
+    let row = prop::collection::vec(prop::sample::select(vec!['.', '#']), 1..5)
+        .prop_map(|row| row.into_iter().collect::<String>());
+
+    prop::collection::vec(row, 1..5).prop_map(|mut rows| {
+        rows[0].replace_range(0..1, "S");
+        rows
+    })
+}
+
+proptest! {
+    #[test]
+    fn generated_inputs_parse(seed in any::<u64>(), size in 0usize..6) {
+        for generator in gen::GENERATORS {
+            let text = generator.generate(seed, size);
+            let solver = advent_2023::solver(generator.day, 1).unwrap();
+
+            prop_assert!(
+                solver.parse(&text).is_ok(),
+                "day {} size {} doesn't parse:\n{}",
+                generator.day,
+                size,
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn day2_most_likely_bag_beats_every_bag_in_range(
+        (games, parsed) in cube_games(),
+        limit in 0u32..=24,
+    ) {
+        let smallest = cubes(&day2::smallest_bag(&parsed));
+        let result = day2::most_likely_bag(&parsed, limit);
+
+        prop_assert_eq!(
+            result.is_some(),
+            smallest.0.max(smallest.1).max(smallest.2) <= limit
+        );
+
+        if let Some((bag, likelihood)) = result {
+            let bag = cubes(&bag);
+            prop_assert!(bag.0 <= limit && bag.1 <= limit && bag.2 <= limit);
+            prop_assert!((naive_bag_likelihood(&games, bag) - likelihood).abs() < 1e-6);
+
+            let best = (smallest.0..=limit)
+                .flat_map(|r| (smallest.1..=limit).map(move |g| (r, g)))
+                .flat_map(|(r, g)| (smallest.2..=limit).map(move |b| (r, g, b)))
+                .map(|bag| naive_bag_likelihood(&games, bag))
+                .fold(f64::NEG_INFINITY, f64::max);
+            prop_assert!((best - likelihood).abs() < 1e-6, "{} vs {}", best, likelihood);
+        }
+    }
+
+    #[test]
+    fn day2_smallest_bag_admits_every_game_and_outliers_force_it((games, parsed) in cube_games()) {
+        let bag = cubes(&day2::smallest_bag(&parsed));
+
+        for &(r, g, b) in games.iter().flatten() {
+            prop_assert!(r <= bag.0 && g <= bag.1 && b <= bag.2);
+        }
+
+        let outliers = day2::outliers(&parsed);
+
+        for (i, _) in games.iter().enumerate() {
+            let mut others = games.clone();
+            others.remove(i);
+            let without = match others.is_empty() {
+                true => (0, 0, 0),
+                false => cubes(&day2::smallest_bag(&parse::<Day2>(&cube_games_text(&others)))),
+            };
+
+            // game ids shift once one is removed, but bags don't depend on them
+            let excess = outliers
+                .iter()
+                .find(|o| o.id as usize == i + 1)
+                .map_or((0, 0, 0), |o| cubes(&o.excess));
+
+            prop_assert_eq!(
+                (without.0 + excess.0, without.1 + excess.1, without.2 + excess.2),
+                bag
+            );
+        }
+    }
+
+    #[test]
+    fn day5_range_minimum_matches_mapping_every_seed((almanac, mapset) in almanac()) {
+        let forward = Day5::part1(&parse::<Day5>(&almanac.expanded_text())).unwrap();
+        let ranged = Day5::part2(&mapset).unwrap();
+
+        prop_assert_eq!(ranged.to_string(), forward.to_string());
+    }
+
+    #[test]
+    fn day5_reverse_lookup_inverts_composed_map((almanac, mapset) in almanac()) {
+        let seed_to_location = mapset.map_between("seed", "location").unwrap();
+
+        for &(start, len) in &almanac.seed_ranges {
+            for seed in start..start + len {
+                let location = seed_to_location.get(seed).unwrap();
+
+                prop_assert_eq!(mapset.seed_for_location(location), Some(seed));
+            }
+        }
+    }
+
+    #[test]
+    fn day7_poker_hands_order_like_textbook_ranking(a in seven_cards(), b in seven_cards()) {
+        let (hand_a, hand_b) = (PokerHand::new(&five(&a)), PokerHand::new(&five(&b)));
+        let (rank_a, rank_b) = (naive_poker_rank(&a[..5]), naive_poker_rank(&b[..5]));
+
+        prop_assert_eq!(poker_category(hand_a.kind()), rank_a.0);
+        prop_assert_eq!(hand_a.cmp(&hand_b), rank_a.cmp(&rank_b));
+    }
+
+    #[test]
+    fn day7_best_hand_is_best_of_every_five(cards in seven_cards()) {
+        let best = cards
+            .iter()
+            .copied()
+            .combinations(5)
+            .max_by_key(|hand| naive_poker_rank(hand))
+            .unwrap();
+
+        prop_assert_eq!(best_hand(&cards), Some(PokerHand::new(&five(&best))));
+    }
+
+    #[test]
+    fn day8_ghost_cycles_match_simulation((network, map) in network()) {
+        let period = network.directions.len();
+
+        for cycle in map.ghost_cycles() {
+            let start = network.names.iter().position(|n| *n == cycle.start).unwrap();
+            let path = network.walk(start, cycle.tail + 2 * cycle.cycle + 1);
+            let state = |step: usize| (path[step], step % period);
+
+            prop_assert!(cycle.cycle > 0);
+
+            // the ghost loops from `tail`, but not from any earlier step
+            for step in cycle.tail..cycle.tail + cycle.cycle {
+                prop_assert_eq!(state(step), state(step + cycle.cycle));
+            }
+            if cycle.tail > 0 {
+                prop_assert_ne!(state(cycle.tail - 1), state(cycle.tail - 1 + cycle.cycle));
+            }
+
+            let z_offsets = (cycle.tail..cycle.tail + cycle.cycle)
+                .filter(|&step| network.names[path[step]].ends_with('Z'))
+                .collect::<Vec<_>>();
+
+            prop_assert_eq!(&cycle.z_offsets, &z_offsets);
+        }
+    }
+
+    #[test]
+    fn day8_components_are_mutually_reachable((network, map) in network()) {
+        let reach = network.reachable();
+        let components = map.components();
+        let component_of = |a: usize| {
+            components.iter().position(|c| c.contains(&network.names[a].as_str())).unwrap()
+        };
+
+        prop_assert_eq!(components.iter().map(Vec::len).sum::<usize>(), network.names.len());
+
+        for (a, from_a) in reach.iter().enumerate() {
+            for (b, from_b) in reach.iter().enumerate() {
+                prop_assert_eq!(component_of(a) == component_of(b), from_a[b] && from_b[a]);
+            }
+        }
+    }
+
+    #[test]
+    fn day19_compiled_workflows_match_interpreter(
+        seed in any::<u64>(),
+        size in 1usize..40,
+        parts in parts(),
+    ) {
+        let text = gen::generator(19).unwrap().generate(seed, size);
+        let system = parse::<Day19>(&text);
+        let program = system.compile();
+
+        for part in parts.iter().chain(system.parts()) {
+            prop_assert_eq!(program.accepts(part), system.accepts(part));
+        }
+    }
+
+    #[test]
+    fn day19_looping_workflows_are_caught(workflows in looping_workflows(), parts in parts()) {
+        let system = parse::<Day19>(&looping_workflows_text(&workflows));
+        let program = system.compile();
+
+        for part in &parts {
+            let expected = naive_looping_accepts(&workflows, part.ratings()[0]);
+
+            prop_assert_eq!(system.accepts(part), expected);
+            prop_assert_eq!(program.accepts(part), expected);
+        }
+    }
+
+    #[test]
+    fn day19_rating_sums_overflow_only_when_the_true_sum_does(
+        parts in prop::collection::vec(
+            prop::collection::vec(
+                prop_oneof![1i64..=4000, i64::MAX - 4000..=i64::MAX, -i64::MAX..=-i64::MAX + 4000],
+                4,
+            ),
+            1..4,
+        ),
+    ) {
+        let text = parts
+            .iter()
+            .map(|r| format!("{{x={},m={},a={},s={}}}", r[0], r[1], r[2], r[3]))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let system = parse::<Day19>(&format!("in{{A}}\n\n{}", text));
+
+        let fits = |sum: i128| i64::try_from(sum).map_err(|_| EvalError::Overflow);
+        let sums = parts
+            .iter()
+            .map(|r| fits(r.iter().map(|&v| v as i128).sum()))
+            .collect::<Vec<_>>();
+
+        for (part, sum) in system.parts().iter().zip(&sums) {
+            prop_assert_eq!(part.rating_number(), *sum);
+        }
+
+        // every part is accepted, so part 1 fails if any sum along the way does
+        let expected = sums
+            .iter()
+            .map(|sum| sum.map(i128::from))
+            .sum::<Result<i128, _>>()
+            .and_then(fits);
+        prop_assert_eq!(
+            Day19::part1(&system).ok().map(|answer| answer.to_string()),
+            expected.ok().map(|total| total.to_string())
+        );
+    }
+
+    #[test]
+    fn day19_conditions_print_as_they_parse(condition in condition()) {
+        prop_assert_eq!(condition.to_string().parse::<Condition>(), Ok(condition));
+    }
+
+    #[test]
+    fn day19_compiled_conditions_match_evaluation(
+        conditions in prop::collection::vec(condition(), 1..4),
+        parts in parts(),
+    ) {
+        let rules = conditions
+            .iter()
+            .enumerate()
+            .map(|(i, condition)| format!("{}:{}", condition, if i % 2 == 0 { "A" } else { "R" }))
+            .collect::<Vec<_>>();
+        let ratings = parts
+            .iter()
+            .map(|part| format!("{{x={},m={},a={},s={}}}", part.ratings()[0], part.ratings()[1], part.ratings()[2], part.ratings()[3]))
+            .collect::<Vec<_>>();
+        let text = format!("in{{{},R}}\n\n{}", rules.join(","), ratings.join("\n"));
+        let system = parse::<Day19>(&text);
+        let program = system.compile();
+
+        for part in system.parts() {
+            let rating = |field: &str| part.ratings()["xmas".find(field).unwrap()];
+            let mut expected = Ok(false);
+            for (i, condition) in conditions.iter().enumerate() {
+                match condition.evaluate(&rating) {
+                    Ok(false) => continue,
+                    Ok(true) => expected = Ok(i % 2 == 0),
+                    Err(e) => expected = Err(e),
+                }
+                break;
+            }
+
+            prop_assert_eq!(system.accepts(part), expected);
+            prop_assert_eq!(program.accepts(part), expected);
+        }
+    }
+
+    #[test]
+    fn day20_extrapolated_pulse_score_matches_every_press(
+        config in circuit(),
+        presses in 0usize..300,
+    ) {
+        let mut pressed = config.clone();
+        let (mut low, mut high) = (0, 0);
+
+        for _ in 0..presses {
+            let counts = pressed.press_button();
+            low += counts.0;
+            high += counts.1;
+        }
+
+        prop_assert_eq!(config.pulse_score(presses), Some(low * high));
+    }
+
+    #[test]
+    fn day20_pulse_score_overflows_only_when_the_product_does(
+        config in circuit(),
+        presses in prop_oneof![1usize..1_000_000_000, 1usize..1_000_000_000_000],
+    ) {
+        let period = config.find_period(1000);
+        prop_assume!(period.is_some());
+        let period = period.unwrap();
+
+        // count the pulses exactly, in u128, a whole period at a time
+        let mut pressed = config.clone();
+        let mut totals = vec![(0u128, 0u128)];
+        for _ in 0..period.start + period.length {
+            let (low, high) = pressed.press_button();
+            let (total_low, total_high) = totals[totals.len() - 1];
+            totals.push((total_low + low as u128, total_high + high as u128));
+        }
+
+        let (low, high) = if presses <= period.start + period.length {
+            totals[presses]
+        } else {
+            let (start, length) = (period.start, period.length);
+            let (cycles, rest) = ((presses - start) / length, (presses - start) % length);
+            let cycles = cycles as u128;
+
+            (
+                totals[start + rest].0 + cycles * (totals[start + length].0 - totals[start].0),
+                totals[start + rest].1 + cycles * (totals[start + length].1 - totals[start].1),
+            )
+        };
+        let expected = usize::try_from(low)
+            .ok()
+            .and(usize::try_from(high).ok())
+            .and_then(|_| usize::try_from(low * high).ok());
+
+        prop_assert_eq!(config.pulse_score(presses), expected);
+    }
+
+    #[test]
+    fn day21_only_rectangular_maps_parse_and_render(rows in garden_rows()) {
+        let text = rows.join("\n");
+        let rectangular = rows.iter().all(|row| row.len() == rows[0].len());
+
+        match render::render_day(21, &text, Style::Plain).unwrap() {
+            Ok(rendered) => {
+                prop_assert!(rectangular);
+                prop_assert_eq!(rendered.lines().count(), rows.len());
+                prop_assert!(rendered.lines().all(|line| line.len() == rows[0].len()));
+            }
+            Err(_) => prop_assert!(!rectangular),
+        }
+    }
+
+    #[test]
+    fn day20_every_module_describes_its_state(config in circuit()) {
+        prop_assert!(config.frames().count() > 0);
+    }
+
+    #[test]
+    fn day20_circuit_returns_to_state_after_period(config in circuit()) {
+        let period = config.find_period(1000);
+        prop_assume!(period.is_some());
+        let period = period.unwrap();
+
+        let mut pressed = config.clone();
+        for _ in 0..period.start {
+            pressed.press_button();
+        }
+
+        let looped = pressed.state();
+        for presses in 1..=period.length {
+            pressed.press_button();
+            prop_assert_eq!(pressed.state() == looped, presses == period.length);
+        }
+    }
+
+    #[test]
+    fn day9_extrapolation_matches_difference_tables((values, sequences) in sequences()) {
+        let next = values.iter().map(|v| naive_next(v)).sum::<isize>();
+        let prev = values.iter().map(|v| naive_prev(v)).sum::<isize>();
+
+        prop_assert_eq!(Day9::part1(&sequences).unwrap().to_string(), next.to_string());
+        prop_assert_eq!(Day9::part2(&sequences).unwrap().to_string(), prev.to_string());
+    }
+
+    #[test]
+    fn day9_short_lines_extrapolate_only_when_differences_reach_zero(
+        values in prop::collection::vec(-3isize..3, 1..=4),
+    ) {
+        match Sequence::new(&values) {
+            Ok(sequence) => {
+                prop_assert!(reaches_zero(&values));
+                prop_assert!(sequence.degree() + 1 < values.len() || sequence.degree() == 0);
+                for (k, value) in values.iter().enumerate() {
+                    prop_assert_eq!(sequence.extrapolate(k as isize), Ok(*value));
+                }
+                prop_assert_eq!(
+                    sequence.extrapolate(values.len() as isize),
+                    Ok(naive_next(&values))
+                );
+                prop_assert_eq!(sequence.extrapolate(-1), Ok(naive_prev(&values)));
+            }
+            Err(e) => {
+                prop_assert_eq!(e, SequenceError::NotPolynomial);
+                prop_assert!(!reaches_zero(&values));
+            }
+        }
+    }
+
+    #[test]
+    fn day11_coordinate_expansion_matches_widened_grid(
+        (grid, star_map) in galaxies(),
+        factor in 0usize..6,
+    ) {
+        prop_assert_eq!(
+            star_map.shortest_paths_with_expansion(factor),
+            naive_expanded_distances(&grid, factor)
+        );
+    }
+
+    #[test]
+    fn day13_reflections_match_slice_comparison((grids, patterns) in patterns()) {
+        let expected = grids.iter().map(|g| naive_reflection_summary(g)).sum::<usize>();
+
+        prop_assert_eq!(Day13::part1(&patterns).unwrap().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn day15_lens_boxes_match_vec_of_boxes(steps in lens_steps()) {
+        let mut boxes = LensBoxes::new();
+        let mut naive = vec![Vec::<(&str, usize)>::new(); 256];
+
+        for (label, focal_length) in steps {
+            let lenses = &mut naive[day15::hash(label)];
+            let pos = lenses.iter().position(|(l, _)| *l == label);
+
+            match (focal_length, pos) {
+                (Some(focal_length), Some(pos)) => lenses[pos].1 = focal_length,
+                (Some(focal_length), None) => lenses.push((label, focal_length)),
+                (None, Some(pos)) => {
+                    lenses.remove(pos);
+                }
+                (None, None) => (),
+            }
+
+            match focal_length {
+                Some(focal_length) => boxes.insert(Lens {
+                    label: label.to_string(),
+                    focal_length,
+                }),
+                None => prop_assert_eq!(boxes.remove(label).is_some(), pos.is_some()),
+            }
+
+            let actual = boxes
+                .iter_boxes()
+                .map(|(n, lenses)| (n, lenses.map(|l| (l.label.as_str(), l.focal_length)).collect()))
+                .collect::<Vec<(usize, Vec<_>)>>();
+            let expected = naive
+                .iter()
+                .cloned()
+                .enumerate()
+                .filter(|(_, lenses)| !lenses.is_empty())
+                .collect::<Vec<_>>();
+
+            prop_assert_eq!(actual, expected);
+        }
+
+        let mut rebuilt = LensBoxes::new();
+        for (label, focal_length) in naive.into_iter().flatten() {
+            rebuilt.insert(Lens {
+                label: label.to_string(),
+                focal_length,
+            });
+        }
+        prop_assert_eq!(boxes, rebuilt);
+    }
+
+    #[test]
+    fn day18_polygon_area_matches_flood_fill((_, dig_map) in trench()) {
+        prop_assert_eq!(
+            dig_map.lagoon_area().to_string(),
+            Day18::part1(&dig_map).unwrap().to_string()
+        );
+    }
+}